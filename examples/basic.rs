@@ -1,15 +1,21 @@
-use imagine::{Application, ClickListener, Imagine, Size, WidgetContext, WidgetId};
+use imagine::{
+    Application, ClickListener, ColorF, Imagine, Size, Theme, WidgetContext, WidgetId,
+};
 use imagine_toolkit::{
-    Button, FillBox, Flex, FlexAlign, FlexDirection, FlexEvent, FlexItem, Label, Padding,
+    Button, Center, FillBox, Flex, FlexAlign, FlexDirection, FlexEvent, FlexItem, Label, Padding,
 };
 
 enum BasicMessage {
     Add,
     Remove,
+    ToggleTheme,
+    OpenWindow,
+    CloseWindow,
 }
 
 struct Basic {
     counter: usize,
+    windows: usize,
     flex: Option<WidgetId>,
 }
 
@@ -24,20 +30,40 @@ impl Application for Basic {
             context.create_widget(Flex::new(rows, FlexDirection::Vertical, FlexAlign::Middle));
         self.flex = Some(flex);
 
-        let add_button = Button::new(context, (0.0, 1.0, 0.0, 1.0), "Add");
+        let add_button =
+            Button::new(context, "Add").with_color(ColorF::new(0.0, 1.0, 0.0, 1.0));
         let add_button = context.create_widget(add_button);
 
-        let remove_button = Button::new(context, (1.0, 0.0, 0.0, 1.0), "Remove");
+        let remove_button =
+            Button::new(context, "Remove").with_color(ColorF::new(1.0, 0.0, 0.0, 1.0));
         let remove_button = context.create_widget(remove_button);
 
+        let theme_button = Button::new(context, "Theme");
+        let theme_button = context.create_widget(theme_button);
+
+        let window_button = Button::new(context, "Window");
+        let window_button = context.create_widget(window_button);
+
         context.add_click_listener(add_button, ClickListener::new(|| BasicMessage::Add));
 
         context.add_click_listener(remove_button, ClickListener::new(|| BasicMessage::Remove));
 
+        context.add_click_listener(
+            theme_button,
+            ClickListener::new(|| BasicMessage::ToggleTheme),
+        );
+
+        context.add_click_listener(
+            window_button,
+            ClickListener::new(|| BasicMessage::OpenWindow),
+        );
+
         let buttons = context.create_widget(Flex::new(
             vec![
                 FlexItem::Flex(add_button, 1),
                 FlexItem::Flex(remove_button, 1),
+                FlexItem::Flex(theme_button, 1),
+                FlexItem::Flex(window_button, 1),
             ],
             FlexDirection::Horizontal,
             FlexAlign::Middle,
@@ -67,6 +93,32 @@ impl Application for Basic {
                 BasicMessage::Remove => {
                     context.send_message(flex, FlexEvent::RemoveChild);
                 }
+                BasicMessage::ToggleTheme => {
+                    let theme = if *context.theme() == Theme::dark() {
+                        Theme::light()
+                    } else {
+                        Theme::dark()
+                    };
+                    context.set_theme(theme);
+                }
+                BasicMessage::OpenWindow => {
+                    self.windows += 1;
+                    let title = format!("Window {}", self.windows);
+                    let window =
+                        context.open_window(title.clone(), Size::new(300.0, 150.0), |context| {
+                            build_window(context, title)
+                        });
+                    context
+                        .window_handle(window)
+                        .set_min_size(Some(Size::new(200.0, 100.0)))
+                        .set_always_on_top(true);
+                }
+                BasicMessage::CloseWindow => {
+                    // Close the window whose button was clicked.
+                    if let Some(window) = context.window() {
+                        context.close_window(window);
+                    }
+                }
             }
         }
     }
@@ -75,6 +127,7 @@ impl Application for Basic {
 fn main() {
     let mut imagine = Imagine::new(Basic {
         counter: 0,
+        windows: 0,
         flex: None,
     });
 
@@ -85,8 +138,9 @@ fn main() {
 fn flex_row(context: &mut WidgetContext<BasicMessage>) -> WidgetId {
     let children = (0..5)
         .map(|_| {
-            let block =
-                context.create_widget(FillBox::new(Size::new(20.0, 20.0), (1.0, 0.0, 0.0, 1.0)));
+            let block = context.create_widget(
+                FillBox::new(Size::new(20.0, 20.0)).with_color(ColorF::new(1.0, 0.0, 0.0, 1.0)),
+            );
             FlexItem::Flex(
                 context.create_widget(Padding::new(2.0, 2.0, 2.0, 2.0, block)),
                 1,
@@ -100,3 +154,18 @@ fn flex_row(context: &mut WidgetContext<BasicMessage>) -> WidgetId {
         FlexAlign::Middle,
     ))
 }
+
+fn build_window(context: &mut WidgetContext<BasicMessage>, title: String) -> WidgetId {
+    let label = context.create_widget(Label::new(title));
+    let label = context.create_widget(Center::new(label));
+
+    let close_button = Button::new(context, "Close");
+    let close_button = context.create_widget(close_button);
+    context.add_click_listener(close_button, ClickListener::new(|| BasicMessage::CloseWindow));
+
+    context.create_widget(Flex::new(
+        vec![FlexItem::Flex(label, 3), FlexItem::Flex(close_button, 1)],
+        FlexDirection::Vertical,
+        FlexAlign::Middle,
+    ))
+}
@@ -1,4 +1,4 @@
-use imagine::{Application, ClickListener, Imagine, Size, WidgetContext, WidgetId};
+use imagine::{Application, ClickListener, ColorF, Imagine, Size, WidgetContext, WidgetId};
 use imagine_toolkit::{
     Button, Flex, FlexAlign, FlexDirection, FlexItem, Label, LabelMessage, Padding,
 };
@@ -197,7 +197,8 @@ fn calc_row(
     let children = items
         .into_iter()
         .map(|(item, flex, message)| {
-            let button = Button::new(context, (0.957, 0.586, 0.16, 1.0), item);
+            let button =
+                Button::new(context, item).with_color(ColorF::new(0.957, 0.586, 0.16, 1.0));
             let button = context.create_widget(button);
             context.add_click_listener(button, ClickListener::new(move || message.clone()));
             FlexItem::Flex(
@@ -1,4 +1,4 @@
-use imagine::{Application, Imagine, Size, WidgetContext, WidgetId};
+use imagine::{Application, ColorF, Imagine, Size, WidgetContext, WidgetId};
 use imagine_toolkit::{FillBox, Flex, FlexAlign, FlexDirection, FlexItem, Label};
 
 struct FlexText;
@@ -9,7 +9,8 @@ impl Application for FlexText {
     fn build(&mut self, context: &mut WidgetContext<Self::Message>) -> WidgetId {
         let left = context.create_widget(Label::new("Left Text"));
         let right = context.create_widget(Label::new("Right Text"));
-        let fill = context.create_widget(FillBox::new(Size::new(10.0, 10.0), (0.0, 0.0, 0.0, 0.0)));
+        let fill = context
+            .create_widget(FillBox::new(Size::new(10.0, 10.0)).with_color(ColorF::TRANSPARENT));
 
         context.create_widget(Flex::new(
             vec![
@@ -0,0 +1,201 @@
+use crate::{Position, Size};
+use std::time::Duration;
+use webrender::api::ColorF;
+
+/// Maps the linear progress of an animation to the progress of the animated value.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// A CSS style cubic Bézier curve from (0, 0) to (1, 1) with the two given control points.
+    CubicBezier(f32, f32, f32, f32),
+}
+
+impl Easing {
+    /// Eases `t`, which is clamped to the 0 to 1 range.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.max(0.0).min(1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => cubic_bezier(0.42, 0.0, 1.0, 1.0, t),
+            Easing::EaseOut => cubic_bezier(0.0, 0.0, 0.58, 1.0, t),
+            Easing::EaseInOut => cubic_bezier(0.42, 0.0, 0.58, 1.0, t),
+            Easing::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, t),
+        }
+    }
+}
+
+/// Solves the curve for the point at `x` and returns its `y`.
+fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+    let bezier = |a: f32, b: f32, s: f32| {
+        3.0 * a * s * (1.0 - s) * (1.0 - s) + 3.0 * b * s * s * (1.0 - s) + s * s * s
+    };
+    let slope = |a: f32, b: f32, s: f32| {
+        3.0 * a * (1.0 - s) * (1.0 - s) + 6.0 * (b - a) * s * (1.0 - s) + 3.0 * (1.0 - b) * s * s
+    };
+
+    // Newton's method converges quickly for the curves used in practice, bisection handles
+    // the flat spots where it doesn't.
+    let mut s = x;
+    for _ in 0..8 {
+        let error = bezier(x1, x2, s) - x;
+        let derivative = slope(x1, x2, s);
+        if error.abs() < 1e-5 {
+            return bezier(y1, y2, s);
+        }
+        if derivative.abs() < 1e-6 {
+            break;
+        }
+        s -= error / derivative;
+    }
+
+    let (mut low, mut high) = (0.0, 1.0);
+    s = x;
+    for _ in 0..32 {
+        let value = bezier(x1, x2, s);
+        if (value - x).abs() < 1e-5 {
+            break;
+        }
+        if value < x {
+            low = s;
+        } else {
+            high = s;
+        }
+        s = (low + high) / 2.0;
+    }
+    bezier(y1, y2, s)
+}
+
+/// A value that can be animated by interpolating between two of its values.
+pub trait Animatable: Copy {
+    /// The value `t` of the way from `self` to `to`. `t` may leave the 0 to 1 range for
+    /// easings that overshoot.
+    fn interpolate(self, to: Self, t: f32) -> Self;
+}
+
+impl Animatable for f32 {
+    fn interpolate(self, to: f32, t: f32) -> f32 {
+        self + (to - self) * t
+    }
+}
+
+impl Animatable for ColorF {
+    fn interpolate(self, to: ColorF, t: f32) -> ColorF {
+        ColorF::new(
+            self.r.interpolate(to.r, t),
+            self.g.interpolate(to.g, t),
+            self.b.interpolate(to.b, t),
+            self.a.interpolate(to.a, t),
+        )
+    }
+}
+
+impl Animatable for Position {
+    fn interpolate(self, to: Position, t: f32) -> Position {
+        Position::new(self.x.interpolate(to.x, t), self.y.interpolate(to.y, t))
+    }
+}
+
+impl Animatable for Size {
+    fn interpolate(self, to: Size, t: f32) -> Size {
+        Size::new(
+            self.width.interpolate(to.width, t),
+            self.height.interpolate(to.height, t),
+        )
+    }
+}
+
+/// A widget property that tweens to every new value it is given.
+///
+/// The widget owning it advances it from `Widget::animate` with `tick`. A tween starts on the
+/// first tick after its target was set, so targets can be set while handling interactions,
+/// which don't know the time.
+#[derive(Copy, Clone, Debug)]
+pub struct Animated<T: Animatable> {
+    value: T,
+    from: T,
+    to: T,
+    duration: Duration,
+    easing: Easing,
+    start: Option<Duration>,
+    running: bool,
+}
+
+impl<T: Animatable> Animated<T> {
+    pub fn new(value: T, duration: Duration, easing: Easing) -> Animated<T> {
+        Animated {
+            value,
+            from: value,
+            to: value,
+            duration,
+            easing,
+            start: None,
+            running: false,
+        }
+    }
+
+    /// The current value.
+    pub fn get(&self) -> T {
+        self.value
+    }
+
+    /// The value the property is animating to, or its value if it isn't animating.
+    pub fn target(&self) -> T {
+        self.to
+    }
+
+    /// Starts animating from the current value to `target`.
+    pub fn set(&mut self, target: T) {
+        self.from = self.value;
+        self.to = target;
+        self.start = None;
+        self.running = true;
+    }
+
+    /// Changes the value immediately, stopping any running animation.
+    pub fn jump(&mut self, value: T) {
+        self.value = value;
+        self.from = value;
+        self.to = value;
+        self.running = false;
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.running
+    }
+
+    /// Advances the animation to `now`, returning whether the value changed and has to be
+    /// rendered.
+    pub fn tick(&mut self, now: Duration) -> bool {
+        if !self.running {
+            return false;
+        }
+
+        let start = *self.start.get_or_insert(now);
+        let elapsed = now.checked_sub(start).unwrap_or_default();
+        let progress = if self.duration == Duration::default() {
+            1.0
+        } else {
+            (elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+        };
+
+        self.value = self.from.interpolate(self.to, self.easing.apply(progress));
+        if progress >= 1.0 {
+            self.value = self.to;
+            self.running = false;
+        }
+        true
+    }
+}
+
+/// The time animations, timers and multi-click detection are driven by, measured from when
+/// the application started.
+///
+/// The event loop sets it from the system clock for every event it handles, `Headless` only
+/// moves it forward when asked to, which makes animations and clicks deterministic in tests.
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct Clock {
+    pub(crate) now: Duration,
+}
@@ -0,0 +1,65 @@
+use ::clipboard::{ClipboardContext, ClipboardProvider};
+
+/// Storage for text that is copied and pasted.
+///
+/// The clipboard lives in the `World` as a resource. Text widgets use it for Ctrl+C, Ctrl+X
+/// and Ctrl+V, and applications can reach it through `WidgetContext::clipboard`.
+pub trait Clipboard: Send + Sync {
+    fn get_contents(&mut self) -> Option<String>;
+
+    fn set_contents(&mut self, contents: String);
+}
+
+/// A clipboard that is private to the application, used by `Headless`.
+#[derive(Debug, Default)]
+pub struct MemoryClipboard {
+    contents: Option<String>,
+}
+
+impl Clipboard for MemoryClipboard {
+    fn get_contents(&mut self) -> Option<String> {
+        self.contents.clone()
+    }
+
+    fn set_contents(&mut self, contents: String) {
+        self.contents = Some(contents);
+    }
+}
+
+/// The operating system's clipboard, used by default for windowed applications.
+///
+/// The connection to the clipboard is opened on first use and kept open, since on X11 copied
+/// text can only be pasted while the application that copied it holds the connection.
+#[derive(Default)]
+pub struct SystemClipboard {
+    context: Option<ClipboardContext>,
+}
+
+impl SystemClipboard {
+    fn context(&mut self) -> Option<&mut ClipboardContext> {
+        if self.context.is_none() {
+            self.context = ClipboardContext::new().ok();
+        }
+        self.context.as_mut()
+    }
+}
+
+impl Clipboard for SystemClipboard {
+    fn get_contents(&mut self) -> Option<String> {
+        self.context()?.get_contents().ok()
+    }
+
+    fn set_contents(&mut self, contents: String) {
+        if let Some(context) = self.context() {
+            context.set_contents(contents).ok();
+        }
+    }
+}
+
+pub(crate) struct ClipboardResource(pub(crate) Box<dyn Clipboard>);
+
+impl Default for ClipboardResource {
+    fn default() -> ClipboardResource {
+        ClipboardResource(Box::new(MemoryClipboard::default()))
+    }
+}
@@ -0,0 +1,284 @@
+use app_units::Au;
+use rusttype::{Font, Scale};
+use std::collections::HashMap;
+use webrender::api::*;
+
+const DEFAULT_FONT_DATA: &[u8] = include_bytes!("../resources/FreeSans.ttf");
+const DEFAULT_FAMILY: &str = "FreeSans";
+
+/// Identifies a font face added to the font registry.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct FontId(usize);
+
+/// The weight of a font face, on the usual 100 to 900 scale.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct FontWeight(pub u16);
+
+impl FontWeight {
+    pub const LIGHT: FontWeight = FontWeight(300);
+    pub const NORMAL: FontWeight = FontWeight(400);
+    pub const MEDIUM: FontWeight = FontWeight(500);
+    pub const BOLD: FontWeight = FontWeight(700);
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum FontStyle {
+    Normal,
+    Italic,
+}
+
+/// Describes how text is drawn: which font to use and at what size.
+///
+/// The family, weight and style are matched against the loaded fonts when the text is laid
+/// out. Characters the family has no glyph for are drawn with the first of the `fallback`
+/// families that has one. If no font of the family was loaded, the bundled FreeSans is used.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextStyle {
+    pub family: String,
+    /// The em size in layout units.
+    pub size: f32,
+    pub weight: FontWeight,
+    pub style: FontStyle,
+    pub fallback: Vec<String>,
+}
+
+impl TextStyle {
+    pub fn new<T: Into<String>>(family: T, size: f32) -> TextStyle {
+        TextStyle {
+            family: family.into(),
+            size,
+            ..TextStyle::default()
+        }
+    }
+
+    pub fn with_size(self, size: f32) -> TextStyle {
+        TextStyle { size, ..self }
+    }
+
+    pub fn with_weight(self, weight: FontWeight) -> TextStyle {
+        TextStyle { weight, ..self }
+    }
+
+    pub fn with_style(self, style: FontStyle) -> TextStyle {
+        TextStyle { style, ..self }
+    }
+
+    /// Adds `family` to the end of the fallback list.
+    pub fn with_fallback<T: Into<String>>(mut self, family: T) -> TextStyle {
+        self.fallback.push(family.into());
+        self
+    }
+}
+
+impl Default for TextStyle {
+    fn default() -> TextStyle {
+        TextStyle {
+            family: DEFAULT_FAMILY.to_string(),
+            size: 32.0,
+            weight: FontWeight::NORMAL,
+            style: FontStyle::Normal,
+            fallback: Vec::new(),
+        }
+    }
+}
+
+/// A font face at a particular size, which is what webrender needs a font instance for.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct FontInstance {
+    pub font: FontId,
+    pub size: Au,
+}
+
+struct Face {
+    family: String,
+    weight: FontWeight,
+    style: FontStyle,
+    data: &'static [u8],
+    font: Font<'static>,
+    // Parsed once for shaping, `None` if the shaper can't read the font.
+    shaper: Option<rustybuzz::Face<'static>>,
+}
+
+/// Every font the application has loaded, shared by all windows.
+///
+/// Lives in the `World` as a resource and always contains the bundled FreeSans, which is used
+/// for any style that doesn't match a loaded family.
+pub(crate) struct FontRegistry {
+    faces: Vec<Face>,
+}
+
+impl FontRegistry {
+    /// Adds a TTF or OTF font to the registry under `family`.
+    pub(crate) fn load(
+        &mut self,
+        family: &str,
+        weight: FontWeight,
+        style: FontStyle,
+        data: Vec<u8>,
+    ) -> Result<FontId, rusttype::Error> {
+        // Fonts are never removed from the registry, which lives as long as the application,
+        // so their data is kept for good and the parsed faces can borrow it.
+        self.add(family, weight, style, Box::leak(data.into_boxed_slice()))
+    }
+
+    fn add(
+        &mut self,
+        family: &str,
+        weight: FontWeight,
+        style: FontStyle,
+        data: &'static [u8],
+    ) -> Result<FontId, rusttype::Error> {
+        let font = Font::from_bytes(data)?;
+        self.faces.push(Face {
+            family: family.to_string(),
+            weight,
+            style,
+            data,
+            font,
+            shaper: rustybuzz::Face::from_slice(data, 0),
+        });
+        Ok(FontId(self.faces.len() - 1))
+    }
+
+    /// Picks the loaded face that best matches `style`, preferring the right slant and then
+    /// the closest weight.
+    pub(crate) fn resolve(&self, style: &TextStyle) -> FontInstance {
+        FontInstance {
+            font: self.find(&style.family, style).unwrap_or(FontId(0)),
+            size: Au::from_f32_px(style.size),
+        }
+    }
+
+    /// The face for `style` followed by the best match from each fallback family that was
+    /// loaded.
+    pub(crate) fn fallback_chain(&self, style: &TextStyle) -> Vec<FontId> {
+        let mut chain = vec![self.resolve(style).font];
+        for family in &style.fallback {
+            if let Some(font) = self.find(family, style) {
+                if !chain.contains(&font) {
+                    chain.push(font);
+                }
+            }
+        }
+        chain
+    }
+
+    fn find(&self, family: &str, style: &TextStyle) -> Option<FontId> {
+        self.faces
+            .iter()
+            .enumerate()
+            .filter(|(_, face)| face.family.eq_ignore_ascii_case(family))
+            .min_by_key(|(_, face)| {
+                let slant = if face.style == style.style { 0 } else { 1 };
+                let weight = (i32::from(face.weight.0) - i32::from(style.weight.0)).abs();
+                (slant, weight)
+            })
+            .map(|(index, _)| FontId(index))
+    }
+
+    pub(crate) fn has_glyph(&self, id: FontId, character: char) -> bool {
+        self.font(id).glyph(character).id().0 != 0
+    }
+
+    pub(crate) fn font(&self, id: FontId) -> &Font<'static> {
+        &self.faces[id.0].font
+    }
+
+    pub(crate) fn data(&self, id: FontId) -> &[u8] {
+        self.faces[id.0].data
+    }
+
+    pub(crate) fn shaper(&self, id: FontId) -> Option<&rustybuzz::Face<'static>> {
+        self.faces[id.0].shaper.as_ref()
+    }
+}
+
+impl Default for FontRegistry {
+    fn default() -> FontRegistry {
+        let mut registry = FontRegistry { faces: Vec::new() };
+        registry
+            .add(
+                DEFAULT_FAMILY,
+                FontWeight::NORMAL,
+                FontStyle::Normal,
+                DEFAULT_FONT_DATA,
+            )
+            .expect("Could not load the default font");
+        registry
+    }
+}
+
+/// The rusttype scale that matches a webrender font instance of `size`.
+///
+/// Webrender sizes fonts by their em square while rusttype scales by the distance from the
+/// descender to the ascender, so the two differ by a per-font ratio.
+pub(crate) fn scale(font: &Font, size: Au) -> Scale {
+    let v_metrics = font.v_metrics_unscaled();
+    let height = (v_metrics.ascent - v_metrics.descent) / f32::from(font.units_per_em());
+    Scale::uniform(size.to_f32_px() * height)
+}
+
+pub(crate) enum FontUpdate {
+    AddFont(FontKey, Vec<u8>),
+    AddInstance(FontInstanceKey, FontKey, Au),
+}
+
+/// The webrender font resources created for one window.
+///
+/// Keys are allocated the first time a font is rendered in the window, and the matching
+/// resource updates are queued until the window sends its next transaction.
+pub(crate) struct FontCache {
+    namespace: IdNamespace,
+    next_key: u32,
+    fonts: HashMap<FontId, FontKey>,
+    instances: HashMap<FontInstance, FontInstanceKey>,
+    updates: Vec<FontUpdate>,
+}
+
+impl FontCache {
+    pub(crate) fn new(namespace: IdNamespace) -> FontCache {
+        FontCache {
+            namespace,
+            next_key: 0,
+            fonts: HashMap::new(),
+            instances: HashMap::new(),
+            updates: Vec::new(),
+        }
+    }
+
+    pub(crate) fn instance_key(
+        &mut self,
+        registry: &FontRegistry,
+        instance: FontInstance,
+    ) -> FontInstanceKey {
+        if let Some(key) = self.instances.get(&instance) {
+            return *key;
+        }
+
+        let font_key = match self.fonts.get(&instance.font) {
+            Some(font_key) => *font_key,
+            None => {
+                let font_key = FontKey(self.namespace, self.next_key());
+                let data = registry.data(instance.font).to_vec();
+                self.updates.push(FontUpdate::AddFont(font_key, data));
+                self.fonts.insert(instance.font, font_key);
+                font_key
+            }
+        };
+
+        let key = FontInstanceKey(self.namespace, self.next_key());
+        self.updates.push(FontUpdate::AddInstance(key, font_key, instance.size));
+        self.instances.insert(instance, key);
+        key
+    }
+
+    /// Resource updates that have to be sent before a display list using the new keys.
+    pub(crate) fn take_updates(&mut self) -> Vec<FontUpdate> {
+        std::mem::take(&mut self.updates)
+    }
+
+    fn next_key(&mut self) -> u32 {
+        self.next_key += 1;
+        self.next_key
+    }
+}
@@ -0,0 +1,479 @@
+use crate::{
+    animation::Clock,
+    call_application,
+    clipboard::ClipboardResource,
+    create_world,
+    font::FontRegistry,
+    handle_messages,
+    hit_test,
+    input,
+    interactive::with_context,
+    invalidate_windows,
+    layout::LayoutStats,
+    render::PaintStats,
+    timer::fire_timers,
+    window::{
+        remove_window, WindowAttributes, WindowCommand, WindowEvent, WindowEvents, WindowRequest,
+        WindowRequests,
+    },
+    Application,
+    Clipboard,
+    EventResponse,
+    FontId,
+    FontStyle,
+    FontWeight,
+    Geometry,
+    InputEvent,
+    KeyEvent,
+    Message,
+    ModifiersState,
+    MouseButton,
+    Position,
+    Size,
+    StyleSheet,
+    Theme,
+    VirtualKeyCode,
+    WheelDelta,
+    WidgetContext,
+    WidgetId,
+    WindowComponent,
+    WindowId,
+};
+use specs::{Builder, Dispatcher, Entity, Join, World};
+use std::{any::Any, collections::HashMap, mem, time::Duration};
+use webrender::api::*;
+use webrender::api::units::*;
+
+/// Runs an `Application` without a window or a webrender instance.
+///
+/// Frames are only produced when `step` is called, which makes it possible to drive the
+/// dispatcher from tests and inspect the resulting display list and widget geometry. Windows
+/// opened with `WidgetContext::open_window` are laid out and rendered alongside the first
+/// one, which input is delivered to, and changes made through a `WindowHandle` are recorded
+/// in each window's `WindowAttributes`. Time stands still for animations and timers until
+/// `advance_time` moves it forward, so they play out the same way on every run.
+pub struct Headless<'a, 'b, A: Application> {
+    world: World,
+    dispatcher: Dispatcher<'a, 'b>,
+    window: Entity,
+    display_lists: HashMap<Entity, BuiltDisplayList>,
+    attributes: HashMap<Entity, WindowAttributes>,
+    application: A,
+    modifiers: ModifiersState,
+    // Whether `on_idle` was called since the last frame.
+    idle: bool,
+    closed: bool,
+}
+
+impl<'a, 'b, A: Application> Headless<'a, 'b, A> {
+    pub fn new(mut application: A, size: Size) -> Headless<'a, 'b, A> {
+        let (mut world, dispatcher) = create_world::<A::Message>();
+        world.add_resource(application.key_bindings());
+        let window = world.create_entity().build();
+        insert_window(&world, window, size, |context| application.build(context));
+        let mut attributes = HashMap::new();
+        attributes.insert(window, WindowAttributes::new("", size));
+        call_application(&world, |context| application.on_start(context));
+
+        Headless {
+            world,
+            dispatcher,
+            window,
+            display_lists: HashMap::new(),
+            attributes,
+            application,
+            modifiers: ModifiersState::default(),
+            idle: false,
+            closed: false,
+        }
+    }
+
+    /// Runs the dispatcher once, handles any queued messages, including those of due timers,
+    /// and, if the window was dirty, finalizes the display list for the frame.
+    pub fn step(&mut self) {
+        *self.world.write_resource::<LayoutStats>() = LayoutStats::default();
+        *self.world.write_resource::<PaintStats>() = PaintStats::default();
+        self.dispatcher.dispatch(&self.world.res);
+        self.world.maintain();
+
+        let timers_fired = fire_timers::<A::Message>(&self.world);
+        if handle_messages(&self.world, &mut self.application) || timers_fired {
+            self.dispatcher.dispatch(&self.world.res);
+            self.world.maintain();
+        }
+
+        self.handle_window_requests();
+
+        let entities = self.world.entities();
+        let mut window_components = self.world.write_storage::<WindowComponent>();
+        let mut animating = false;
+        let mut rendered = false;
+        for (entity, window_component) in (&entities, &mut window_components).join() {
+            animating |= window_component.animating();
+            if !window_component.dirty() {
+                continue;
+            }
+            window_component.set_dirty(false);
+            rendered = true;
+
+            // There is no webrender instance to add the fonts to.
+            window_component.fonts.take_updates();
+
+            if let Some(builder) = window_component.display_list_builder.take() {
+                let (_, _, display_list) = builder.finalize();
+                self.display_lists.insert(entity, display_list);
+            }
+        }
+        drop(window_components);
+        drop(entities);
+
+        // The event loop would wait for the next event now.
+        if !animating && !rendered && !self.idle {
+            let (world, application) = (&self.world, &mut self.application);
+            call_application(world, |context| application.on_idle(context));
+        }
+        self.idle = !animating && !rendered;
+    }
+
+    fn handle_window_requests(&mut self) {
+        let requests = mem::take(&mut self.world.write_resource::<WindowRequests<A::Message>>().0);
+        if requests.is_empty() {
+            return;
+        }
+
+        for request in requests {
+            match request {
+                WindowRequest::Open {
+                    window,
+                    attributes,
+                    build,
+                } => {
+                    insert_window(&self.world, window.0, attributes.size, build);
+                    self.attributes.insert(window.0, attributes);
+                }
+                WindowRequest::Update(window, command) => {
+                    if let Some(attributes) = self.attributes.get_mut(&window.0) {
+                        attributes.apply(&command);
+                        // A real window reports the new size back, which lays it out again.
+                        if let WindowCommand::SetSize(size) = command {
+                            self.resize_window(window.0, size);
+                        }
+                    }
+                }
+                WindowRequest::Close(window) => {
+                    if window.0 == self.window {
+                        self.exit();
+                    }
+                    self.display_lists.remove(&window.0);
+                    self.attributes.remove(&window.0);
+                    remove_window(&self.world, window);
+                }
+                WindowRequest::Quit => self.exit(),
+            }
+        }
+        self.world.maintain();
+
+        // Lay out and render the opened windows right away, as the event loop does.
+        self.dispatcher.dispatch(&self.world.res);
+        self.world.maintain();
+    }
+
+    /// Moves the clock animations and timers run on forward by `duration` and steps a frame,
+    /// firing every timer that became due.
+    pub fn advance_time(&mut self, duration: Duration) {
+        self.world.write_resource::<Clock>().now += duration;
+        self.step();
+    }
+
+    /// Whether a widget's animation changed in the last frame, i.e. whether the windowed
+    /// event loop would keep rendering frames.
+    pub fn is_animating(&self) -> bool {
+        self.world
+            .read_storage::<WindowComponent>()
+            .get(self.window)
+            .expect("Could not find window component")
+            .animating()
+    }
+
+    /// Delivers an input event to the window and steps a frame, the same way the windowed
+    /// event loop dispatches after every event it receives.
+    pub fn inject(&mut self, input: InputEvent) {
+        let response = input::handle_input(&self.world, self.window, input);
+
+        match response {
+            EventResponse::CloseRequested => self.request_close(),
+            // Only the widgets the input changed are laid out again.
+            EventResponse::Dirty => self
+                .world
+                .write_storage::<WindowComponent>()
+                .get_mut(self.window)
+                .expect("Could not find window component")
+                .set_dirty(true),
+            EventResponse::ToggleProfiler | EventResponse::Continue => {}
+        }
+
+        self.step();
+    }
+
+    pub fn move_cursor(&mut self, position: Position) {
+        self.inject(InputEvent::CursorMoved(position));
+    }
+
+    pub fn mouse_down(&mut self, button: MouseButton) {
+        self.inject(InputEvent::MouseDown(button));
+    }
+
+    pub fn mouse_up(&mut self, button: MouseButton) {
+        self.inject(InputEvent::MouseUp(button));
+    }
+
+    /// Moves the cursor to `position` and presses and releases the left button there.
+    pub fn click(&mut self, position: Position) {
+        self.move_cursor(position);
+        self.mouse_down(MouseButton::Left);
+        self.mouse_up(MouseButton::Left);
+    }
+
+    /// Presses the left button at `from`, moves the cursor to `to` and releases it there,
+    /// which starts and drops a drag if a widget under `from` provides a payload.
+    pub fn drag(&mut self, from: Position, to: Position) {
+        self.move_cursor(from);
+        self.mouse_down(MouseButton::Left);
+        self.move_cursor(to);
+        self.mouse_up(MouseButton::Left);
+    }
+
+    /// Clicks twice at `position`, quickly enough to count as a double click.
+    pub fn double_click(&mut self, position: Position) {
+        self.click(position);
+        self.click(position);
+    }
+
+    /// Adds a TTF or OTF font that `TextStyle`s can select by `family`, `weight` and `style`.
+    pub fn load_font(
+        &mut self,
+        family: &str,
+        weight: FontWeight,
+        style: FontStyle,
+        data: Vec<u8>,
+    ) -> Result<FontId, rusttype::Error> {
+        self.world
+            .write_resource::<FontRegistry>()
+            .load(family, weight, style, data)
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.world.add_resource(theme);
+        invalidate_windows(&self.world);
+    }
+
+    pub fn set_style_sheet(&mut self, style_sheet: StyleSheet) {
+        self.world.add_resource(style_sheet);
+        invalidate_windows(&self.world);
+    }
+
+    /// Passes `message` to the widget's `Widget::update`, as `WidgetContext::send_message`
+    /// does, and steps a frame.
+    pub fn send_message<T: Any>(&mut self, widget_id: WidgetId, message: T) {
+        call_application(&self.world, |context: &mut WidgetContext<A::Message>| {
+            context.send_message(widget_id, message)
+        });
+        self.step();
+    }
+
+    pub fn set_clipboard<C: Clipboard + 'static>(&mut self, clipboard: C) {
+        self.world.add_resource(ClipboardResource(Box::new(clipboard)));
+    }
+
+    pub fn clipboard_contents(&self) -> Option<String> {
+        self.world
+            .write_resource::<ClipboardResource>()
+            .0
+            .get_contents()
+    }
+
+    /// Scrolls the mouse wheel by `delta` at the current cursor position.
+    pub fn scroll(&mut self, delta: WheelDelta) {
+        self.inject(InputEvent::Wheel(delta));
+    }
+
+    /// Sets the modifier state reported with subsequent key events.
+    pub fn set_modifiers(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+    }
+
+    pub fn key_down(&mut self, key: VirtualKeyCode) {
+        let key_event = KeyEvent::new(Some(key), 0, self.modifiers);
+        self.inject(InputEvent::KeyDown(key_event));
+    }
+
+    pub fn key_up(&mut self, key: VirtualKeyCode) {
+        let key_event = KeyEvent::new(Some(key), 0, self.modifiers);
+        self.inject(InputEvent::KeyUp(key_event));
+    }
+
+    /// Presses and releases `key`.
+    pub fn press_key(&mut self, key: VirtualKeyCode) {
+        self.key_down(key);
+        self.key_up(key);
+    }
+
+    /// Returns the topmost interactive widget at `position`, as of the last frame.
+    pub fn hit_test(&self, position: Position) -> Option<WidgetId> {
+        hit_test::hit_test(&self.world, self.root(), position).map(WidgetId)
+    }
+
+    /// The widget that currently has keyboard focus.
+    pub fn focused(&self) -> Option<WidgetId> {
+        self.world
+            .read_storage::<WindowComponent>()
+            .get(self.window)
+            .expect("Could not find window component")
+            .focused
+            .map(WidgetId)
+    }
+
+    /// Asks the application whether the first window may close, as its close button does,
+    /// and closes it if so.
+    pub fn request_close(&mut self) {
+        let window = WindowId(self.window);
+        let (world, application) = (&self.world, &mut self.application);
+        let close =
+            call_application(world, |context| application.on_close_requested(window, context));
+        if close {
+            self.exit();
+        }
+    }
+
+    /// Whether the first window was closed, e.g. by pressing Escape, or the application quit.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    fn exit(&mut self) {
+        if !self.closed {
+            self.closed = true;
+            let (world, application) = (&self.world, &mut self.application);
+            call_application(world, |context| application.on_exit(context));
+        }
+    }
+
+    pub fn resize(&mut self, size: Size) {
+        self.resize_window(self.window, size);
+    }
+
+    fn resize_window(&mut self, window: Entity, size: Size) {
+        if let Some(attributes) = self.attributes.get_mut(&window) {
+            attributes.size = size;
+        }
+        if let Some(window_component) =
+            self.world.write_storage::<WindowComponent>().get_mut(window)
+        {
+            window_component.layout_size = LayoutSize::new(size.width, size.height);
+            window_component.set_dirty(true);
+        }
+    }
+
+    /// Reports `event` for the first window to the application, as the windowed event loop
+    /// does when the system moves, focuses or resizes a window, and steps a frame.
+    pub fn window_event(&mut self, event: WindowEvent) {
+        if let WindowEvent::Resized(size) = event {
+            self.resize(size);
+        }
+        self.world
+            .write_resource::<WindowEvents>()
+            .0
+            .push((WindowId(self.window), event));
+        self.step();
+    }
+
+    /// The attributes of an open window, including the changes made through its
+    /// `WindowHandle`.
+    pub fn window_attributes(&self, window: WindowId) -> Option<&WindowAttributes> {
+        self.attributes.get(&window.0)
+    }
+
+    /// Marks the window dirty so the next `step` lays out every widget and renders the widget
+    /// tree again.
+    pub fn invalidate(&mut self) {
+        self.world
+            .write_storage::<WindowComponent>()
+            .get_mut(self.window)
+            .expect("Could not find window component")
+            .invalidate_layout();
+    }
+
+    /// How many widgets were laid out, and how many kept their size from an earlier layout,
+    /// during the last `step`.
+    pub fn layout_stats(&self) -> LayoutStats {
+        *self.world.read_resource::<LayoutStats>()
+    }
+
+    /// How many widgets were rendered, and how many replayed the display items they recorded
+    /// in an earlier frame, during the last `step`.
+    pub fn paint_stats(&self) -> PaintStats {
+        *self.world.read_resource::<PaintStats>()
+    }
+
+    pub fn root(&self) -> WidgetId {
+        self.world
+            .read_storage::<WindowComponent>()
+            .get(self.window)
+            .expect("Could not find window component")
+            .root
+    }
+
+    /// The display list produced by the most recent frame that rendered.
+    pub fn display_list(&self) -> Option<&BuiltDisplayList> {
+        self.display_lists.get(&self.window)
+    }
+
+    /// Like `display_list`, for any open window.
+    pub fn window_display_list(&self, window: WindowId) -> Option<&BuiltDisplayList> {
+        self.display_lists.get(&window.0)
+    }
+
+    /// The first window, which input is delivered to.
+    pub fn window(&self) -> WindowId {
+        WindowId(self.window)
+    }
+
+    /// Every open window, including the first.
+    pub fn windows(&self) -> Vec<WindowId> {
+        let entities = self.world.entities();
+        let window_components = self.world.read_storage::<WindowComponent>();
+        (&entities, &window_components)
+            .join()
+            .map(|(entity, _)| WindowId(entity))
+            .collect()
+    }
+
+    /// Returns the geometry of a widget in window coordinates as of the last layout.
+    pub fn geometry(&self, widget_id: WidgetId) -> Option<Geometry> {
+        hit_test::geometry(&self.world, self.root(), widget_id.0)
+    }
+
+    pub fn application(&self) -> &A {
+        &self.application
+    }
+
+    pub fn application_mut(&mut self) -> &mut A {
+        &mut self.application
+    }
+}
+
+/// Builds a window's widgets without a renderer, giving it the pipeline `Imagine` would.
+fn insert_window<M, F>(world: &World, window: Entity, size: Size, build: F)
+where
+    M: Message,
+    F: FnOnce(&mut WidgetContext<M>) -> WidgetId,
+{
+    let root = with_context(world, build);
+    let mut window_component =
+        WindowComponent::new(root, PipelineId(0, window.id()), IdNamespace(0));
+    window_component.layout_size = LayoutSize::new(size.width, size.height);
+    world
+        .write_storage::<WindowComponent>()
+        .insert(window, window_component)
+        .ok();
+}
@@ -0,0 +1,222 @@
+use crate::{
+    interactive::Interactive, widget::WidgetComponent, Geometry, Position, Size, WidgetId,
+};
+use specs::{Entity, ReadStorage, World};
+use std::any::Any;
+
+#[derive(Copy, Clone, Debug)]
+struct Rect {
+    origin: Position,
+    size: Size,
+}
+
+impl Rect {
+    fn contains(&self, point: Position) -> bool {
+        point.x >= self.origin.x
+            && point.y >= self.origin.y
+            && point.x < self.origin.x + self.size.width
+            && point.y < self.origin.y + self.size.height
+    }
+
+    fn intersection(&self, other: &Rect) -> Rect {
+        let x0 = self.origin.x.max(other.origin.x);
+        let y0 = self.origin.y.max(other.origin.y);
+        let x1 = (self.origin.x + self.size.width).min(other.origin.x + other.size.width);
+        let y1 = (self.origin.y + self.size.height).min(other.origin.y + other.size.height);
+        Rect {
+            origin: Position::new(x0, y0),
+            size: Size::new((x1 - x0).max(0.0), (y1 - y0).max(0.0)),
+        }
+    }
+}
+
+/// Finds the topmost interactive widget under `point` using the geometry computed by the
+/// last layout.
+pub(crate) fn hit_test(world: &World, root: WidgetId, point: Position) -> Option<Entity> {
+    let interactive = world.read_storage::<Interactive>();
+    widgets_at(world, root, point)
+        .into_iter()
+        .rev()
+        .find(|entity| interactive.get(*entity).is_some())
+}
+
+/// Finds the topmost widget under `point` that wants mouse wheel input.
+pub(crate) fn scroll_target(world: &World, root: WidgetId, point: Position) -> Option<Entity> {
+    let widgets = world.read_storage::<WidgetComponent>();
+    widgets_at(world, root, point)
+        .into_iter()
+        .rev()
+        .find(|entity| widgets.get(*entity).is_some_and(|widget| widget.scrollable()))
+}
+
+/// Finds the topmost widget under `point` that accepts `payload` as a drop.
+pub(crate) fn drop_target(
+    world: &World,
+    root: WidgetId,
+    point: Position,
+    payload: &dyn Any,
+) -> Option<Entity> {
+    let widgets = world.read_storage::<WidgetComponent>();
+    widgets_at(world, root, point)
+        .into_iter()
+        .rev()
+        .find(|entity| widgets.get(*entity).is_some_and(|widget| widget.accepts_drop(payload)))
+}
+
+/// Returns the widgets from `root` down to and including `target`, or nothing if `target` is
+/// not in the tree.
+pub(crate) fn path_to(world: &World, root: WidgetId, target: Entity) -> Vec<Entity> {
+    let widgets = world.read_storage::<WidgetComponent>();
+
+    fn find(
+        children: &[WidgetId],
+        target: Entity,
+        widgets: &ReadStorage<WidgetComponent>,
+        path: &mut Vec<Entity>,
+    ) -> bool {
+        for child in children {
+            path.push(child.0);
+            if child.0 == target {
+                return true;
+            }
+            if let Some(widget) = widgets.get(child.0) {
+                if find(&widget.children(), target, widgets, path) {
+                    return true;
+                }
+            }
+            path.pop();
+        }
+        false
+    }
+
+    let mut path = Vec::new();
+    find(&[root], target, &widgets, &mut path);
+    path
+}
+
+/// Collects every widget whose bounds contain `point`, in paint order.
+///
+/// A later sibling or a descendant comes after the widgets painted before it. Widgets that
+/// clip their children hide any part of a descendant that falls outside of the clip.
+fn widgets_at(world: &World, root: WidgetId, point: Position) -> Vec<Entity> {
+    let positions = world.read_storage::<Position>();
+    let sizes = world.read_storage::<Size>();
+    let widgets = world.read_storage::<WidgetComponent>();
+
+    fn visit(
+        children: &[WidgetId],
+        point: Position,
+        offset: Position,
+        clip: Option<Rect>,
+        data: &(
+            &ReadStorage<Position>,
+            &ReadStorage<Size>,
+            &ReadStorage<WidgetComponent>,
+        ),
+        hits: &mut Vec<Entity>,
+    ) {
+        if clip.is_some_and(|clip| !clip.contains(point)) {
+            return;
+        }
+
+        let (positions, sizes, widgets) = data;
+        for child in children {
+            let (position, size, widget) = match (
+                positions.get(child.0),
+                sizes.get(child.0),
+                widgets.get(child.0),
+            ) {
+                (Some(position), Some(size), Some(widget)) => (position, size, widget),
+                _ => continue,
+            };
+
+            let bounds = Rect {
+                origin: Position::new(offset.x + position.x, offset.y + position.y),
+                size: *size,
+            };
+
+            if bounds.contains(point) {
+                hits.push(child.0);
+            }
+
+            let geometry = Geometry::new(bounds.origin, bounds.size);
+            let child_clip = match widget.children_clip(geometry) {
+                Some(area) => {
+                    let area = Rect {
+                        origin: area.position,
+                        size: area.size,
+                    };
+                    Some(clip.map_or(area, |clip| clip.intersection(&area)))
+                }
+                None => clip,
+            };
+
+            visit(
+                &widget.children(),
+                point,
+                bounds.origin,
+                child_clip,
+                data,
+                hits,
+            );
+        }
+    }
+
+    let mut hits = Vec::new();
+    visit(
+        &[root],
+        point,
+        Position::zero(),
+        None,
+        &(&positions, &sizes, &widgets),
+        &mut hits,
+    );
+    hits
+}
+
+/// Returns the geometry of `target` in window coordinates as of the last layout.
+pub(crate) fn geometry(world: &World, root: WidgetId, target: Entity) -> Option<Geometry> {
+    let positions = world.read_storage::<Position>();
+    let sizes = world.read_storage::<Size>();
+    let widgets = world.read_storage::<WidgetComponent>();
+
+    fn find(
+        children: &[WidgetId],
+        target: Entity,
+        offset: Position,
+        data: &(
+            &ReadStorage<Position>,
+            &ReadStorage<Size>,
+            &ReadStorage<WidgetComponent>,
+        ),
+    ) -> Option<Geometry> {
+        let (positions, sizes, widgets) = data;
+        for child in children {
+            let (position, size, widget) = match (
+                positions.get(child.0),
+                sizes.get(child.0),
+                widgets.get(child.0),
+            ) {
+                (Some(position), Some(size), Some(widget)) => (position, size, widget),
+                _ => continue,
+            };
+            let position = Position::new(offset.x + position.x, offset.y + position.y);
+
+            if child.0 == target {
+                return Some(Geometry::new(position, *size));
+            }
+
+            if let Some(geometry) = find(&widget.children(), target, position, data) {
+                return Some(geometry);
+            }
+        }
+        None
+    }
+
+    find(
+        &[root],
+        target,
+        Position::zero(),
+        &(&positions, &sizes, &widgets),
+    )
+}
@@ -0,0 +1,563 @@
+use crate::{
+    animation::Clock,
+    hit_test::{drop_target, geometry, hit_test, path_to, scroll_target},
+    interactive::{
+        DragPayload, Event, Interaction, KeyEvent, MouseEvent, MouseMoveEvent, WheelDelta,
+    },
+    layout::NeedsLayout,
+    text::FinalText,
+    widget::WidgetComponent,
+    EventResponse, Position, WidgetId, WindowComponent,
+};
+use glutin::{ModifiersState, MouseButton, VirtualKeyCode};
+use specs::{Entity, ReadStorage, World};
+use std::time::Duration;
+
+// Presses closer together than this, in time and in layout units, count as one multi-click.
+const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+const MULTI_CLICK_DISTANCE: f32 = 4.0;
+// How far the cursor has to move from a press before a drag starts.
+const DRAG_THRESHOLD: f32 = 4.0;
+
+/// Input delivered to a window.
+///
+/// The windowed runtime translates glutin events into `InputEvent`s, and `Headless` lets
+/// tests inject them directly. Positions are in layout coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputEvent {
+    CursorMoved(Position),
+    MouseDown(MouseButton),
+    MouseUp(MouseButton),
+    KeyDown(KeyEvent),
+    KeyUp(KeyEvent),
+    Character(char),
+    Wheel(WheelDelta),
+}
+
+/// The last mouse press in a window, used to count double and triple clicks.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Click {
+    button: MouseButton,
+    position: Position,
+    // The time on the animation clock.
+    time: Duration,
+    count: u32,
+}
+
+impl Click {
+    fn follows(&self, button: MouseButton, position: Position, time: Duration) -> bool {
+        self.button == button
+            && time.saturating_sub(self.time) <= MULTI_CLICK_INTERVAL
+            && (position.x - self.position.x).abs() <= MULTI_CLICK_DISTANCE
+            && (position.y - self.position.y).abs() <= MULTI_CLICK_DISTANCE
+    }
+}
+
+/// The widget that received a press holds the pointer until the button is released.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Capture {
+    entity: Entity,
+    button: MouseButton,
+    origin: Position,
+    // Whether the widgets have already been asked to start a drag for this press.
+    drag_requested: bool,
+}
+
+/// A drag-and-drop in progress, started from `source`.
+pub(crate) struct Drag {
+    source: Entity,
+    payload: DragPayload,
+    target: Option<Entity>,
+}
+
+/// What dragging away from a press started.
+enum DragStart {
+    Payload(Drag),
+    /// A widget that follows the pointer itself, e.g. to scroll, takes over the capture.
+    Claimed(Entity),
+}
+
+/// A key pressed together with exactly the given modifiers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct KeyBinding {
+    pub key: VirtualKeyCode,
+    pub modifiers: ModifiersState,
+}
+
+impl KeyBinding {
+    pub fn new(key: VirtualKeyCode, modifiers: ModifiersState) -> KeyBinding {
+        KeyBinding { key, modifiers }
+    }
+
+    fn matches(&self, key_event: KeyEvent) -> bool {
+        key_event.key == Some(self.key) && key_event.modifiers == self.modifiers
+    }
+}
+
+/// Keys the runtime handles itself when the focused widget does not use them.
+///
+/// Returned by `Application::key_bindings`; set a binding to `None` to pass that key through
+/// to the focused widget instead.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct KeyBindings {
+    pub quit: Option<KeyBinding>,
+    pub toggle_profiler: Option<KeyBinding>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> KeyBindings {
+        KeyBindings {
+            quit: Some(KeyBinding::new(
+                VirtualKeyCode::Escape,
+                ModifiersState::default(),
+            )),
+            toggle_profiler: Some(KeyBinding::new(
+                VirtualKeyCode::P,
+                ModifiersState {
+                    ctrl: true,
+                    shift: true,
+                    ..ModifiersState::default()
+                },
+            )),
+        }
+    }
+}
+
+pub(crate) fn handle_input(world: &World, window: Entity, input: InputEvent) -> EventResponse {
+    let mut window_components = world.write_storage::<WindowComponent>();
+    let window_component = window_components
+        .get_mut(window)
+        .expect("Could not find window component");
+
+    match input {
+        InputEvent::KeyDown(mut key_event) => {
+            if let Some(key) = key_event.key {
+                key_event.repeat = window_component.pressed_keys.contains(&key);
+                if !key_event.repeat {
+                    window_component.pressed_keys.push(key);
+                }
+            }
+            if key_event.key == Some(VirtualKeyCode::Tab) {
+                let reverse = key_event.modifiers.shift;
+                let next = next_focus(world, window_component, reverse);
+                return set_focus(world, window_component, next);
+            }
+            if focused_handles_key(world, window_component, key_event) {
+                if let Some(command) = clipboard_command(key_event) {
+                    return send_to_focused(world, window_component, command);
+                }
+            } else {
+                let key_bindings = world.read_resource::<KeyBindings>();
+                if key_bindings
+                    .quit
+                    .is_some_and(|binding| binding.matches(key_event))
+                {
+                    return EventResponse::CloseRequested;
+                }
+                if key_bindings
+                    .toggle_profiler
+                    .is_some_and(|binding| binding.matches(key_event))
+                {
+                    return EventResponse::ToggleProfiler;
+                }
+            }
+
+            send_to_focused(world, window_component, Interaction::KeyDown(key_event))
+        }
+        InputEvent::KeyUp(key_event) => {
+            window_component
+                .pressed_keys
+                .retain(|key| Some(*key) != key_event.key);
+            send_to_focused(world, window_component, Interaction::KeyUp(key_event))
+        }
+        InputEvent::Character(character) => {
+            send_to_focused(world, window_component, Interaction::Character(character))
+        }
+        InputEvent::Wheel(delta) => {
+            let position = window_component.cursor_position;
+            match scroll_target(world, window_component.root, position) {
+                Some(entity) => {
+                    let mut events = world.write_storage::<Event>();
+                    Event::push(&mut events, entity, Interaction::Wheel(delta));
+                    EventResponse::Dirty
+                }
+                None => EventResponse::Continue,
+            }
+        }
+        InputEvent::CursorMoved(position) => {
+            let previous = window_component.cursor_position;
+            window_component.cursor_position = position;
+
+            if let Some(mut capture) = window_component.captured {
+                let mut previous = previous;
+                let distance = (position.x - capture.origin.x)
+                    .abs()
+                    .max((position.y - capture.origin.y).abs());
+                if !capture.drag_requested
+                    && capture.button == MouseButton::Left
+                    && distance > DRAG_THRESHOLD
+                {
+                    capture.drag_requested = true;
+                    match start_drag(world, window_component.root, capture.entity) {
+                        Some(DragStart::Payload(drag)) => window_component.drag = Some(drag),
+                        Some(DragStart::Claimed(entity)) if entity != capture.entity => {
+                            // The pressed widget is released, and the one that claimed the
+                            // drag moves along with the pointer from where it was pressed.
+                            let click_count =
+                                window_component.last_click.map_or(1, |click| click.count);
+                            let mouse_event = mouse_event(
+                                world,
+                                window_component,
+                                capture.entity,
+                                capture.button,
+                                click_count,
+                            );
+                            Event::push(
+                                &mut world.write_storage::<Event>(),
+                                capture.entity,
+                                Interaction::MouseUp(mouse_event),
+                            );
+                            capture.entity = entity;
+                            previous = capture.origin;
+                        }
+                        _ => {}
+                    }
+                    window_component.captured = Some(capture);
+                }
+
+                let mouse_move = MouseMoveEvent {
+                    position: local_position(world, window_component, capture.entity),
+                    dx: position.x - previous.x,
+                    dy: position.y - previous.y,
+                };
+                Event::push(
+                    &mut world.write_storage::<Event>(),
+                    capture.entity,
+                    Interaction::MouseMove(mouse_move),
+                );
+                update_drop_target(world, window_component);
+                return EventResponse::Dirty;
+            }
+
+            let hit = hit_test(world, window_component.root, position);
+            let changed = hit != window_component.hovered;
+
+            if changed {
+                let mut events = world.write_storage::<Event>();
+                if let Some(old) = window_component.hovered {
+                    Event::push(&mut events, old, Interaction::Hovered(false));
+                }
+                if let Some(new) = hit {
+                    Event::push(&mut events, new, Interaction::Hovered(true));
+                }
+            }
+
+            window_component.hovered = hit;
+
+            if changed {
+                EventResponse::Dirty
+            } else {
+                EventResponse::Continue
+            }
+        }
+        InputEvent::MouseDown(button) => {
+            let position = window_component.cursor_position;
+            let now = world.read_resource::<Clock>().now;
+            let click_count = match window_component.last_click {
+                Some(click) if click.follows(button, position, now) => click.count + 1,
+                _ => 1,
+            };
+            window_component.last_click = Some(Click {
+                button,
+                position,
+                time: now,
+                count: click_count,
+            });
+
+            if button == MouseButton::Left {
+                let focus = window_component.hovered.filter(|entity| {
+                    world
+                        .read_storage::<WidgetComponent>()
+                        .get(*entity)
+                        .is_some_and(|widget| widget.focusable())
+                });
+                set_focus(world, window_component, focus);
+            }
+
+            if let Some(entity) = window_component.hovered {
+                let mouse_event = mouse_event(world, window_component, entity, button, click_count);
+                let mut events = world.write_storage::<Event>();
+                Event::push(&mut events, entity, Interaction::MouseDown(mouse_event));
+                if window_component.captured.is_none() {
+                    window_component.captured = Some(Capture {
+                        entity,
+                        button,
+                        origin: position,
+                        drag_requested: false,
+                    });
+                }
+            }
+            EventResponse::Dirty
+        }
+        InputEvent::MouseUp(button) => {
+            // The release goes to the widget that was pressed, even if the cursor left it.
+            let mut clicked = false;
+            let target = match window_component.captured {
+                Some(capture) if capture.button == button => {
+                    window_component.captured = None;
+                    let released_over = hit_test(
+                        world,
+                        window_component.root,
+                        window_component.cursor_position,
+                    );
+                    clicked = button == MouseButton::Left
+                        && !capture.drag_requested
+                        && released_over == Some(capture.entity);
+                    Some(capture.entity)
+                }
+                _ => window_component.hovered,
+            };
+            if let Some(entity) = target {
+                let click_count = window_component
+                    .last_click
+                    .filter(|click| click.button == button)
+                    .map_or(1, |click| click.count);
+                let mouse_event = mouse_event(world, window_component, entity, button, click_count);
+                let mut events = world.write_storage::<Event>();
+                Event::push(&mut events, entity, Interaction::MouseUp(mouse_event));
+                if clicked {
+                    Event::push(&mut events, entity, Interaction::Click);
+                }
+            }
+            if window_component.captured.is_none() {
+                if let Some(drag) = window_component.drag.take() {
+                    finish_drag(world, window_component, drag);
+                }
+            }
+            EventResponse::Dirty
+        }
+    }
+}
+
+/// Returns the cursor position relative to `entity`'s geometry.
+fn local_position(world: &World, window_component: &WindowComponent, entity: Entity) -> Position {
+    let cursor = window_component.cursor_position;
+    match geometry(world, window_component.root, entity) {
+        Some(geometry) => Position::new(
+            cursor.x - geometry.position.x,
+            cursor.y - geometry.position.y,
+        ),
+        None => cursor,
+    }
+}
+
+fn mouse_event(
+    world: &World,
+    window_component: &WindowComponent,
+    entity: Entity,
+    button: MouseButton,
+    click_count: u32,
+) -> MouseEvent {
+    let position = local_position(world, window_component, entity);
+    MouseEvent {
+        button,
+        position,
+        click_count,
+        text_hit: world
+            .read_storage::<FinalText>()
+            .get(entity)
+            .and_then(|final_text| final_text.hit_test(position)),
+    }
+}
+
+/// Asks the pressed widget and then its ancestors for a drag payload, stopping at the first
+/// one that provides it or claims the drag for itself.
+fn start_drag(world: &World, root: WidgetId, pressed: Entity) -> Option<DragStart> {
+    let path = path_to(world, root, pressed);
+    let mut widgets = world.write_storage::<WidgetComponent>();
+
+    let mut child = pressed;
+    for entity in path.into_iter().rev() {
+        if let Some(widget) = widgets.get_mut(entity) {
+            if let Some(payload) = widget.start_drag(WidgetId(child)) {
+                // The source may have hidden or removed the dragged child.
+                world
+                    .write_storage::<NeedsLayout>()
+                    .insert(entity, NeedsLayout)
+                    .ok();
+                return Some(DragStart::Payload(Drag {
+                    source: entity,
+                    payload,
+                    target: None,
+                }));
+            }
+            if widget.claims_drag() {
+                return Some(DragStart::Claimed(entity));
+            }
+        }
+        child = entity;
+    }
+    None
+}
+
+/// Finds the widget under the cursor that accepts the current drag and tells the old and new
+/// targets when it changes.
+fn update_drop_target(world: &World, window_component: &mut WindowComponent) {
+    let root = window_component.root;
+    let cursor = window_component.cursor_position;
+    let drag = match window_component.drag.as_mut() {
+        Some(drag) => drag,
+        None => return,
+    };
+
+    let target = drop_target(world, root, cursor, &*drag.payload);
+    if target != drag.target {
+        let mut events = world.write_storage::<Event>();
+        if let Some(old) = drag.target {
+            Event::push(&mut events, old, Interaction::DragOver(false));
+        }
+        if let Some(new) = target {
+            Event::push(&mut events, new, Interaction::DragOver(true));
+        }
+        drag.target = target;
+    }
+}
+
+/// Hands the payload to the drop target, if there is one, and tells the source how the drag
+/// ended.
+fn finish_drag(world: &World, window_component: &WindowComponent, drag: Drag) {
+    let dropped = match drag.target {
+        Some(target) => {
+            let position = local_position(world, window_component, target);
+            Event::push(
+                &mut world.write_storage::<Event>(),
+                target,
+                Interaction::DragOver(false),
+            );
+            match world.write_storage::<WidgetComponent>().get_mut(target) {
+                Some(widget) => {
+                    widget.drop_payload(drag.payload, position);
+                    world
+                        .write_storage::<NeedsLayout>()
+                        .insert(target, NeedsLayout)
+                        .ok();
+                    true
+                }
+                None => false,
+            }
+        }
+        None => false,
+    };
+
+    Event::push(
+        &mut world.write_storage::<Event>(),
+        drag.source,
+        Interaction::DragEnd(dropped),
+    );
+}
+
+/// Whether the focused widget uses `key_event` itself, before any key binding applies.
+fn focused_handles_key(
+    world: &World,
+    window_component: &WindowComponent,
+    key_event: KeyEvent,
+) -> bool {
+    window_component.focused.is_some_and(|entity| {
+        world
+            .read_storage::<WidgetComponent>()
+            .get(entity)
+            .is_some_and(|widget| widget.handles_key(key_event))
+    })
+}
+
+/// The clipboard command a key event stands for, e.g. Ctrl+C or Cmd+C for copy.
+fn clipboard_command(key_event: KeyEvent) -> Option<Interaction> {
+    if !(key_event.modifiers.ctrl || key_event.modifiers.logo) {
+        return None;
+    }
+    match key_event.key {
+        Some(VirtualKeyCode::C) => Some(Interaction::Copy),
+        Some(VirtualKeyCode::X) => Some(Interaction::Cut),
+        Some(VirtualKeyCode::V) => Some(Interaction::Paste),
+        _ => None,
+    }
+}
+
+fn send_to_focused(
+    world: &World,
+    window_component: &WindowComponent,
+    interaction: Interaction,
+) -> EventResponse {
+    match window_component.focused {
+        Some(entity) if world.entities().is_alive(entity) => {
+            Event::push(&mut world.write_storage::<Event>(), entity, interaction);
+            EventResponse::Dirty
+        }
+        _ => EventResponse::Continue,
+    }
+}
+
+/// Moves focus to `focus`, notifying the widgets that lost and gained it.
+fn set_focus(
+    world: &World,
+    window_component: &mut WindowComponent,
+    focus: Option<Entity>,
+) -> EventResponse {
+    if focus == window_component.focused {
+        return EventResponse::Continue;
+    }
+
+    let mut events = world.write_storage::<Event>();
+    if let Some(old) = window_component.focused {
+        if world.entities().is_alive(old) {
+            Event::push(&mut events, old, Interaction::FocusLost);
+        }
+    }
+    if let Some(new) = focus {
+        Event::push(&mut events, new, Interaction::FocusGained);
+    }
+
+    window_component.focused = focus;
+    EventResponse::Dirty
+}
+
+/// Finds the focusable widget after (or before, when `reverse`) the focused one in tree
+/// order, wrapping around at either end.
+fn next_focus(world: &World, window_component: &WindowComponent, reverse: bool) -> Option<Entity> {
+    let widgets = world.read_storage::<WidgetComponent>();
+
+    fn collect(
+        children: &[WidgetId],
+        widgets: &ReadStorage<WidgetComponent>,
+        order: &mut Vec<Entity>,
+    ) {
+        for child in children {
+            if let Some(widget) = widgets.get(child.0) {
+                if widget.focusable() {
+                    order.push(child.0);
+                }
+                collect(&widget.children(), widgets, order);
+            }
+        }
+    }
+
+    let mut order = Vec::new();
+    collect(&[window_component.root], &widgets, &mut order);
+
+    if order.is_empty() {
+        return None;
+    }
+
+    let current = window_component
+        .focused
+        .and_then(|focused| order.iter().position(|entity| *entity == focused));
+
+    let index = match (current, reverse) {
+        (Some(index), false) => (index + 1) % order.len(),
+        (Some(index), true) => (index + order.len() - 1) % order.len(),
+        (None, false) => 0,
+        (None, true) => order.len() - 1,
+    };
+
+    Some(order[index])
+}
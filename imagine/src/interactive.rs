@@ -1,43 +1,190 @@
-use crate::{Widget, WidgetComponent, WidgetId};
-use specs::{Component, DenseVecStorage, Entities, WriteStorage};
-use std::any::Any;
+use crate::{
+    animation::Clock,
+    clipboard::{Clipboard, ClipboardResource},
+    layout::NeedsLayout,
+    text::TextHit,
+    timer::{TimerId, Timers},
+    window::{WindowAttributes, WindowHandle, WindowId, WindowRequest, WindowRequests},
+    Position, Size, Theme, Widget, WidgetComponent, WidgetId,
+};
+use glutin::{ModifiersState, MouseButton, VirtualKeyCode};
+use specs::{Component, DenseVecStorage, Entities, Entity, World, WriteStorage};
+use std::{any::Any, time::Duration};
 
 pub trait Message: Any + Send + Sync {}
 
 impl<T> Message for T where T: Any + Send + Sync {}
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Interaction {
     Hovered(bool),
-    MouseDown,
-    MouseUp,
+    MouseDown(MouseEvent),
+    MouseUp(MouseEvent),
+    /// Sent after `MouseUp` when the left button was pressed and released over this widget
+    /// without starting a drag.
+    Click,
+    KeyDown(KeyEvent),
+    KeyUp(KeyEvent),
+    FocusGained,
+    FocusLost,
+    Character(char),
+    Copy,
+    Cut,
+    Paste,
+    Wheel(WheelDelta),
+    /// Cursor movement while this widget has captured the pointer, from the press until the
+    /// button is released.
+    MouseMove(MouseMoveEvent),
+    /// Sent to a widget that accepts the current drag when the cursor enters or leaves it.
+    DragOver(bool),
+    /// Sent to the source of a drag when it ends, with whether the payload was dropped.
+    DragEnd(bool),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MouseEvent {
+    pub button: MouseButton,
+    /// The cursor position relative to the widget's geometry.
+    pub position: Position,
+    /// How many clicks in quick succession this press belongs to, e.g. 2 for a double click.
+    pub click_count: u32,
+    /// The character under the cursor in the text the widget last laid out with
+    /// `LayoutContext::set_text`, with the text's top left corner at the widget's.
+    pub text_hit: Option<TextHit>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MouseMoveEvent {
+    /// The cursor position relative to the widget's geometry.
+    pub position: Position,
+    /// The movement since the previous `MouseMove`, in layout units.
+    pub dx: f32,
+    pub dy: f32,
+}
+
+/// Data carried by a drag, returned by `Widget::start_drag` and handed to
+/// `Widget::drop_payload`.
+pub type DragPayload = Box<dyn Any + Send + Sync>;
+
+// Pixels scrolled per line reported by the mouse wheel.
+const PIXELS_PER_LINE: f32 = 32.0;
+
+/// Mouse wheel movement as glutin reports it, positive when scrolling up or right.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WheelDelta {
+    Lines(f32, f32),
+    Pixels(f32, f32),
+}
+
+impl WheelDelta {
+    pub fn lines(self) -> (f32, f32) {
+        match self {
+            WheelDelta::Lines(x, y) => (x, y),
+            WheelDelta::Pixels(x, y) => (x / PIXELS_PER_LINE, y / PIXELS_PER_LINE),
+        }
+    }
+
+    pub fn pixels(self) -> (f32, f32) {
+        match self {
+            WheelDelta::Lines(x, y) => (x * PIXELS_PER_LINE, y * PIXELS_PER_LINE),
+            WheelDelta::Pixels(x, y) => (x, y),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct KeyEvent {
+    pub key: Option<VirtualKeyCode>,
+    pub scancode: u32,
+    pub modifiers: ModifiersState,
+    /// Whether the key was already held down, i.e. the press was repeated by the system.
+    pub repeat: bool,
+}
+
+impl KeyEvent {
+    pub fn new(key: Option<VirtualKeyCode>, scancode: u32, modifiers: ModifiersState) -> KeyEvent {
+        KeyEvent {
+            key,
+            scancode,
+            modifiers,
+            repeat: false,
+        }
+    }
+
+    /// Whether the key activates a focused widget like a click, i.e. Enter or Space.
+    pub fn is_activation(&self) -> bool {
+        matches!(
+            self.key,
+            Some(VirtualKeyCode::Return)
+                | Some(VirtualKeyCode::NumpadEnter)
+                | Some(VirtualKeyCode::Space)
+        )
+    }
 }
 
 pub struct WidgetContext<'a, 'b, M: Message> {
     pub(crate) entities: &'a Entities<'b>,
     pub(crate) widgets: &'a mut WriteStorage<'b, WidgetComponent>,
+    pub(crate) needs_layout: &'a mut WriteStorage<'b, NeedsLayout>,
     pub(crate) click_listeners: &'a mut WriteStorage<'b, ClickListener<M>>,
+    pub(crate) event_listeners: &'a mut WriteStorage<'b, EventListener<M>>,
+    pub(crate) clipboard: &'a mut ClipboardResource,
+    pub(crate) theme: &'a mut Theme,
+    pub(crate) timers: &'a mut Timers<M>,
+    pub(crate) window_requests: &'a mut WindowRequests<M>,
+    // The time on the animation clock, which timers are scheduled relative to.
+    now: Duration,
+    theme_changed: bool,
+    widgets_changed: bool,
+    // The window the message being handled came from.
+    pub(crate) window: Option<WindowId>,
 }
 
-impl<'a, 'b, M: Message> WidgetContext<'a, 'b, M> {
-    pub(crate) fn new(
-        entities: &'a Entities<'b>,
-        widgets: &'a mut WriteStorage<'b, WidgetComponent>,
-        click_listeners: &'a mut WriteStorage<'b, ClickListener<M>>,
-    ) -> WidgetContext<'a, 'b, M> {
-        WidgetContext {
-            entities,
-            widgets,
-            click_listeners,
-        }
-    }
+/// Runs `f` with a context over the widgets and resources in `world`.
+pub(crate) fn with_context<M, R, F>(world: &World, f: F) -> R
+where
+    M: Message,
+    F: FnOnce(&mut WidgetContext<M>) -> R,
+{
+    let entities = world.entities();
+    let mut widgets = world.write_storage::<WidgetComponent>();
+    let mut needs_layout = world.write_storage::<NeedsLayout>();
+    let mut click_listeners = world.write_storage::<ClickListener<M>>();
+    let mut event_listeners = world.write_storage::<EventListener<M>>();
+    let mut clipboard = world.write_resource::<ClipboardResource>();
+    let mut theme = world.write_resource::<Theme>();
+    let mut timers = world.write_resource::<Timers<M>>();
+    let mut window_requests = world.write_resource::<WindowRequests<M>>();
+    let mut context = WidgetContext {
+        entities: &entities,
+        widgets: &mut widgets,
+        needs_layout: &mut needs_layout,
+        click_listeners: &mut click_listeners,
+        event_listeners: &mut event_listeners,
+        clipboard: &mut clipboard,
+        theme: &mut theme,
+        timers: &mut timers,
+        window_requests: &mut window_requests,
+        now: world.read_resource::<Clock>().now,
+        theme_changed: false,
+        widgets_changed: false,
+        window: None,
+    };
+    f(&mut context)
+}
 
+impl<'a, 'b, M: Message> WidgetContext<'a, 'b, M> {
+    /// Passes `message` to the widget's `Widget::update`, laying the widget out again in the
+    /// next frame, along with its descendants if the message changed the widget's own theme.
     pub fn send_message<T: Any>(&mut self, widget_id: WidgetId, message: T) {
-        let removed = if let Some(widget) = self.widgets.get_mut(widget_id.0) {
-            widget.update(Box::new(message))
+        let (removed, theme_changed) = if let Some(widget) = self.widgets.get_mut(widget_id.0) {
+            let theme = widget.theme().cloned();
+            let removed = widget.update(Box::new(message));
+            (removed, widget.theme() != theme.as_ref())
         } else {
-            None
+            (None, false)
         };
+        self.invalidate_layout(widget_id);
 
         fn remove_widgets<'a, 'b>(
             entities: &'a Entities<'b>,
@@ -54,12 +201,127 @@ impl<'a, 'b, M: Message> WidgetContext<'a, 'b, M> {
         if let Some(removed) = removed {
             remove_widgets(self.entities, self.widgets, &removed);
         }
+
+        // The descendants inherit the theme, which their layout may depend on.
+        if theme_changed {
+            self.invalidate_descendants(widget_id);
+        }
+    }
+
+    fn invalidate_descendants(&mut self, widget_id: WidgetId) {
+        let children = match self.widgets.get(widget_id.0) {
+            Some(widget) => widget.children(),
+            None => return,
+        };
+        for child in children {
+            self.needs_layout.insert(child.0, NeedsLayout).ok();
+            self.invalidate_descendants(child);
+        }
+    }
+
+    /// Adds a class that style sheet rules can select the widget by, e.g. `.primary`.
+    pub fn add_class<T: Into<String>>(&mut self, widget_id: WidgetId, class: T) {
+        if let Some(widget) = self.widgets.get_mut(widget_id.0) {
+            widget.classes.push(class.into());
+        }
+        self.invalidate_layout(widget_id);
+    }
+
+    /// Lays the widget out again in the next frame, e.g. after state it shares with the
+    /// application changed. Widgets are otherwise laid out again only when they handle a
+    /// message or an interaction that changes their layout.
+    pub fn invalidate_layout(&mut self, widget_id: WidgetId) {
+        self.needs_layout.insert(widget_id.0, NeedsLayout).ok();
+        self.widgets_changed = true;
     }
 
     pub fn add_click_listener(&mut self, widget_id: WidgetId, listener: ClickListener<M>) {
         self.click_listeners.insert(widget_id.0, listener).ok();
     }
 
+    pub fn add_event_listener(&mut self, widget_id: WidgetId, listener: EventListener<M>) {
+        self.event_listeners.insert(widget_id.0, listener).ok();
+    }
+
+    pub fn clipboard(&mut self) -> &mut dyn Clipboard {
+        &mut *self.clipboard.0
+    }
+
+    pub fn theme(&self) -> &Theme {
+        self.theme
+    }
+
+    /// Replaces the theme every window is drawn with, e.g. to switch between light and dark.
+    pub fn set_theme(&mut self, theme: Theme) {
+        *self.theme = theme;
+        self.theme_changed = true;
+    }
+
+    pub(crate) fn theme_changed(&self) -> bool {
+        self.theme_changed
+    }
+
+    pub(crate) fn widgets_changed(&self) -> bool {
+        self.widgets_changed
+    }
+
+    /// Sends `message` to the application once, after `delay`.
+    pub fn set_timeout(&mut self, delay: Duration, message: M) -> TimerId {
+        self.timers.once(self.now + delay, message)
+    }
+
+    /// Sends the message returned by `message` to the application every `interval`, until
+    /// the timer is cancelled.
+    pub fn set_interval<F>(&mut self, interval: Duration, message: F) -> TimerId
+    where
+        F: Fn() -> M + Send + Sync + 'static,
+    {
+        self.timers.repeat(self.now + interval, interval, message)
+    }
+
+    /// Cancels a timer so it sends no further messages. Timers that already fired are
+    /// ignored.
+    pub fn cancel_timer(&mut self, timer: TimerId) {
+        self.timers.cancel(timer);
+    }
+
+    /// The window the message being handled came from, `None` for messages sent by timers
+    /// and while building a window.
+    pub fn window(&self) -> Option<WindowId> {
+        self.window
+    }
+
+    /// Opens a new window whose widgets are created by `build`, once the current messages
+    /// are handled.
+    pub fn open_window<T, F>(&mut self, title: T, size: Size, build: F) -> WindowId
+    where
+        T: Into<String>,
+        F: FnOnce(&mut WidgetContext<M>) -> WidgetId + Send + Sync + 'static,
+    {
+        let window = WindowId(self.entities.create());
+        self.window_requests.0.push(WindowRequest::Open {
+            window,
+            attributes: WindowAttributes::new(title, size),
+            build: Box::new(build),
+        });
+        window
+    }
+
+    /// Closes every window and exits, without asking `Application::on_close_requested`.
+    pub fn quit(&mut self) {
+        self.window_requests.0.push(WindowRequest::Quit);
+    }
+
+    /// Changes the title, size, position and other attributes of a window.
+    pub fn window_handle(&mut self, window: WindowId) -> WindowHandle<M> {
+        WindowHandle::new(window, self.window_requests)
+    }
+
+    /// Closes a window and removes its widgets, once the current messages are handled.
+    pub fn close_window(&mut self, window: WindowId) {
+        self.window_requests.0.push(WindowRequest::Close(window));
+    }
+
     pub fn remove_widget(&mut self, widget_id: WidgetId) {
         self.entities.delete(widget_id.0).ok();
     }
@@ -71,6 +333,7 @@ impl<'a, 'b, M: Message> WidgetContext<'a, 'b, M> {
                 .with(
                     WidgetComponent {
                         inner: Box::new(widget),
+                        classes: Vec::new(),
                     },
                     self.widgets,
                 )
@@ -98,13 +361,48 @@ impl<M: Message> Component for ClickListener<M> {
     type Storage = DenseVecStorage<Self>;
 }
 
+/// Turns events emitted by a widget through `Widget::take_events` into application messages.
+///
+/// Events of any type other than `E` are ignored.
+pub struct EventListener<M: Message> {
+    pub(crate) on_event: Box<dyn Fn(&dyn Any) -> Option<M> + Send + Sync + 'static>,
+}
+
+impl<M: Message> EventListener<M> {
+    pub fn new<E, F>(listener: F) -> EventListener<M>
+    where
+        E: Any,
+        F: Fn(&E) -> M + Send + Sync + 'static,
+    {
+        EventListener {
+            on_event: Box::new(move |event: &dyn Any| event.downcast_ref::<E>().map(&listener)),
+        }
+    }
+}
+
+impl<M: Message> Component for EventListener<M> {
+    type Storage = DenseVecStorage<Self>;
+}
+
 pub(crate) struct Event {
-    pub(crate) event: Interaction,
+    pub(crate) events: Vec<Interaction>,
 }
 
 impl Event {
-    pub(crate) fn new(event: Interaction) -> Event {
-        Event { event }
+    /// Queues `interaction` for `entity`, keeping any interactions already queued this frame.
+    pub(crate) fn push(events: &mut WriteStorage<Event>, entity: Entity, interaction: Interaction) {
+        if let Some(event) = events.get_mut(entity) {
+            event.events.push(interaction);
+        } else {
+            events
+                .insert(
+                    entity,
+                    Event {
+                        events: vec![interaction],
+                    },
+                )
+                .ok();
+        }
     }
 }
 
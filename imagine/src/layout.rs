@@ -1,6 +1,11 @@
-use crate::{text::FinalText, WidgetComponent, WidgetId};
-use rusttype::Font;
-use specs::{Component, DenseVecStorage, ReadStorage, WriteStorage};
+use crate::{
+    font::{FontRegistry, TextStyle},
+    render::NeedsPaint,
+    style::StyledTheme,
+    text::{FinalText, ParagraphStyle, RichText},
+    Theme, WidgetComponent, WidgetId,
+};
+use specs::{Component, DenseVecStorage, Entity, NullStorage, ReadStorage, WriteStorage};
 
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub struct Geometry {
@@ -98,31 +103,104 @@ impl Component for Size {
     type Storage = DenseVecStorage<Self>;
 }
 
+/// Marks a widget whose layout changed, e.g. because it handled a message, so it is laid out
+/// again instead of reusing its size from the last layout.
+#[derive(Default)]
+pub(crate) struct NeedsLayout;
+
+impl Component for NeedsLayout {
+    type Storage = NullStorage<Self>;
+}
+
+/// What a widget was last laid out with, which decides whether its size can be reused.
+pub(crate) struct LayoutCache {
+    constraint: BoxConstraint,
+    // The widget whose layout laid this one out, `None` for the root of a window.
+    parent: Option<Entity>,
+}
+
+impl LayoutCache {
+    pub(crate) fn parent(&self) -> Option<Entity> {
+        self.parent
+    }
+}
+
+impl Component for LayoutCache {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// How much work layout did since the counters were last reset, which happens at the start of
+/// every frame.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct LayoutStats {
+    /// Widgets whose `Widget::layout` was called.
+    pub laid_out: usize,
+    /// Widgets whose size from an earlier layout was reused.
+    pub reused: usize,
+}
+
+/// The storages a `LayoutContext` reads and writes.
+pub(crate) struct LayoutStorage<'a, 'b> {
+    pub(crate) positions: &'a mut WriteStorage<'b, Position>,
+    pub(crate) sizes: &'a mut WriteStorage<'b, Size>,
+    pub(crate) text: &'a mut WriteStorage<'b, FinalText>,
+    pub(crate) caches: &'a mut WriteStorage<'b, LayoutCache>,
+    pub(crate) needs_layout: &'a mut WriteStorage<'b, NeedsLayout>,
+    pub(crate) needs_paint: &'a mut WriteStorage<'b, NeedsPaint>,
+    pub(crate) widgets: &'a ReadStorage<'b, WidgetComponent>,
+    pub(crate) styled: &'a ReadStorage<'b, StyledTheme>,
+}
+
 pub struct LayoutContext<'a, 'b> {
     positions: &'a mut WriteStorage<'b, Position>,
     sizes: &'a mut WriteStorage<'b, Size>,
     text: &'a mut WriteStorage<'b, FinalText>,
+    caches: &'a mut WriteStorage<'b, LayoutCache>,
+    needs_layout: &'a mut WriteStorage<'b, NeedsLayout>,
+    needs_paint: &'a mut WriteStorage<'b, NeedsPaint>,
     widgets: &'a ReadStorage<'b, WidgetComponent>,
-    font: &'a Font<'static>,
+    styled: &'a ReadStorage<'b, StyledTheme>,
+    fonts: &'a FontRegistry,
+    theme: &'a Theme,
+    stats: &'a mut LayoutStats,
+    // Themes of the ancestors of the widget being laid out that override `theme`.
+    theme_overrides: Vec<&'a Theme>,
+    // The ancestors of the widget being laid out.
+    parents: Vec<Entity>,
+    // Whether sizes from earlier layouts are ignored, e.g. because the theme changed.
+    forced: bool,
 }
 
 impl<'a, 'b> LayoutContext<'a, 'b> {
     pub(crate) fn new(
-        positions: &'a mut WriteStorage<'b, Position>,
-        sizes: &'a mut WriteStorage<'b, Size>,
-        text: &'a mut WriteStorage<'b, FinalText>,
-        widgets: &'a ReadStorage<'b, WidgetComponent>,
-        font: &'a Font<'static>,
+        storage: LayoutStorage<'a, 'b>,
+        fonts: &'a FontRegistry,
+        theme: &'a Theme,
+        stats: &'a mut LayoutStats,
     ) -> LayoutContext<'a, 'b> {
         LayoutContext {
-            positions,
-            sizes,
-            text,
-            widgets,
-            font,
+            positions: storage.positions,
+            sizes: storage.sizes,
+            text: storage.text,
+            caches: storage.caches,
+            needs_layout: storage.needs_layout,
+            needs_paint: storage.needs_paint,
+            widgets: storage.widgets,
+            styled: storage.styled,
+            fonts,
+            theme,
+            stats,
+            theme_overrides: Vec::new(),
+            parents: Vec::new(),
+            forced: false,
         }
     }
 
+    /// The theme of the widget being laid out.
+    pub fn theme(&self) -> &Theme {
+        self.theme_overrides.last().copied().unwrap_or(self.theme)
+    }
+
     pub fn set_position(&mut self, widget: WidgetId, position: Position) {
         self.positions.insert(widget.0, position).ok();
     }
@@ -131,21 +209,176 @@ impl<'a, 'b> LayoutContext<'a, 'b> {
         *self.sizes.get(widget.0).unwrap()
     }
 
-    pub fn layout_text(&self, text: &str) -> FinalText {
-        FinalText::new(self.font, text)
+    /// Lays out `text` without wrapping, starting a new line at every newline.
+    pub fn layout_text(&self, text: &str, style: &TextStyle) -> FinalText {
+        self.layout_paragraph(text, style, &ParagraphStyle::default(), std::f32::INFINITY)
+    }
+
+    /// Lays out `text` to fit within `max_width`, wrapping, aligning and truncating it as
+    /// `paragraph` asks.
+    pub fn layout_paragraph(
+        &self,
+        text: &str,
+        style: &TextStyle,
+        paragraph: &ParagraphStyle,
+        max_width: f32,
+    ) -> FinalText {
+        FinalText::new(self.fonts, style, text, paragraph, max_width)
+    }
+
+    /// Lays out the spans of `text` like `layout_paragraph`, with each span's attributes
+    /// applied on top of `style`.
+    pub fn layout_rich_text(
+        &self,
+        text: &RichText,
+        style: &TextStyle,
+        paragraph: &ParagraphStyle,
+        max_width: f32,
+    ) -> FinalText {
+        FinalText::rich(self.fonts, style, text, paragraph, max_width)
     }
 
     pub fn set_text(&mut self, widget: WidgetId, text: FinalText) {
         self.text.insert(widget.0, text).ok();
     }
 
+    /// Lays out a widget within `box_constraint` and returns its size.
+    ///
+    /// The size from the widget's last layout is returned instead if it was laid out with the
+    /// same constraint and has not been marked as needing layout since.
     pub fn layout_widget(&mut self, widget_id: WidgetId, box_constraint: BoxConstraint) -> Size {
+        let parent = self.parents.last().copied();
+        if !self.forced && !self.needs_layout.contains(widget_id.0) {
+            let cached = self
+                .caches
+                .get(widget_id.0)
+                .filter(|cache| cache.constraint == box_constraint)
+                .and_then(|_| self.sizes.get(widget_id.0))
+                .copied();
+            if let Some(size) = cached {
+                // The widget may have been moved to another parent, e.g. by a drop.
+                if let Some(cache) = self.caches.get_mut(widget_id.0) {
+                    cache.parent = parent;
+                }
+                self.stats.reused += 1;
+                return size;
+            }
+        }
+
         let widget = self
             .widgets
             .get(widget_id.0)
             .expect("Could not find widget during layout.");
+        let theme = self
+            .styled
+            .get(widget_id.0)
+            .map(|styled| &styled.0)
+            .or_else(|| widget.theme());
+        if let Some(theme) = theme {
+            self.theme_overrides.push(theme);
+        }
+        self.parents.push(widget_id.0);
         let size = widget.layout(widget_id, self, box_constraint);
+        self.parents.pop();
+        if theme.is_some() {
+            self.theme_overrides.pop();
+        }
+
         self.sizes.insert(widget_id.0, size).ok();
+        self.caches
+            .insert(
+                widget_id.0,
+                LayoutCache {
+                    constraint: box_constraint,
+                    parent,
+                },
+            )
+            .ok();
+        self.needs_layout.remove(widget_id.0);
+        // What the widget renders may depend on anything its layout does.
+        self.needs_paint.insert(widget_id.0, NeedsPaint).ok();
+        self.stats.laid_out += 1;
         size
     }
+
+    /// Lays out the root of a window, reusing every size that is still valid unless `forced`.
+    pub(crate) fn layout_root(
+        &mut self,
+        root: WidgetId,
+        box_constraint: BoxConstraint,
+        forced: bool,
+    ) {
+        self.forced = forced;
+        self.layout_widget(root, box_constraint);
+        self.forced = false;
+        self.positions.insert(root.0, Position::zero()).ok();
+    }
+
+    /// Lays out the widgets of the tree under `root` that were marked as needing layout.
+    ///
+    /// A widget's new size can move its siblings, so its parent is laid out again too, and so
+    /// on up to the nearest relayout boundary: an ancestor whose constraint is tight, which
+    /// keeps its size whatever its children do, or the root.
+    pub(crate) fn layout_invalidated(&mut self, root: WidgetId, invalidated: &[Entity]) {
+        let mut boundaries = Vec::new();
+        for &entity in invalidated {
+            if !self.needs_layout.contains(entity) {
+                continue;
+            }
+
+            // The widget followed by its ancestors, as of their last layout.
+            let mut path = vec![entity];
+            let mut boundary = None;
+            while let Some(cache) = self.caches.get(*path.last().unwrap()) {
+                if boundary.is_none() && cache.constraint.is_tight() {
+                    boundary = Some(path.len() - 1);
+                }
+                match cache.parent {
+                    // The parents of detached widgets are stale and may lead back to them.
+                    Some(parent) if !path.contains(&parent) => path.push(parent),
+                    _ => break,
+                }
+            }
+            // Widgets that were never laid out or are no longer in this tree are laid out
+            // once they are reached from the root.
+            if *path.last().unwrap() != root.0 || self.caches.get(root.0).is_none() {
+                continue;
+            }
+
+            let boundary = boundary.unwrap_or(path.len() - 1);
+            for &ancestor in &path[1..=boundary] {
+                self.needs_layout.insert(ancestor, NeedsLayout).ok();
+            }
+            boundaries.push(path.split_off(boundary));
+        }
+
+        // Boundaries nearer to the root first, which lays out any boundary below them that is
+        // still marked along the way.
+        boundaries.sort_by_key(|path| path.len());
+        for path in boundaries {
+            let boundary = path[0];
+            if !self.needs_layout.contains(boundary) {
+                continue;
+            }
+            let constraint = self.caches.get(boundary).unwrap().constraint;
+            for &ancestor in path[1..].iter().rev() {
+                if let Some(theme) = self.widget_theme(ancestor) {
+                    self.theme_overrides.push(theme);
+                }
+            }
+            self.parents.extend(path.get(1).copied());
+            self.layout_widget(WidgetId(boundary), constraint);
+            self.parents.clear();
+            self.theme_overrides.clear();
+        }
+    }
+
+    fn widget_theme(&self, entity: Entity) -> Option<&'a Theme> {
+        let styled = self.styled;
+        let widgets = self.widgets;
+        styled
+            .get(entity)
+            .map(|styled| &styled.0)
+            .or_else(|| widgets.get(entity).and_then(|widget| widget.theme()))
+    }
 }
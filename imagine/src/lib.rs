@@ -1,51 +1,122 @@
+mod animation;
+mod clipboard;
+mod font;
+mod headless;
+mod hit_test;
+mod input;
 mod interactive;
 mod layout;
 mod render;
+mod shaping;
+mod style;
 mod systems;
 pub mod text;
+mod theme;
+mod timer;
 mod widget;
+mod window;
 
 use self::{
-    interactive::{Event, Interactive},
-    systems::{InteractionSystem, LayoutSystem, RenderSystem},
+    animation::Clock,
+    clipboard::ClipboardResource,
+    font::{FontCache, FontRegistry, FontUpdate},
+    input::{Capture, Click, Drag},
+    interactive::with_context,
+    style::StyleWatcher,
+    systems::{AnimationSystem, InteractionSystem, LayoutSystem, RenderSystem, StyleSystem},
+    timer::{fire_timers, TimerWaker, Timers},
     widget::WidgetComponent,
+    window::{remove_window, WindowCommand, WindowEvents, WindowRequest, WindowRequests},
 };
-use app_units::Au;
 use gleam::gl;
 use glutin::GlContext;
 use glutin::{EventsLoop, WindowBuilder};
-use rusttype::Font;
 use specs::{
     Builder, Component, DenseVecStorage, Dispatcher, DispatcherBuilder, Entity, Join, World,
 };
 use std::collections::HashMap;
 use std::mem;
+use std::path::Path;
+use std::time::Instant;
 use webrender::api::*;
 use webrender::api::units::*;
 
 pub use self::{
-    interactive::{ClickListener, Interaction, Message, WidgetContext},
-    layout::{BoxConstraint, Geometry, LayoutContext, Position, Size},
-    render::RenderContext,
-    widget::{Widget, WidgetId},
+    animation::{Animatable, Animated, Easing},
+    clipboard::{Clipboard, MemoryClipboard, SystemClipboard},
+    font::{FontId, FontInstance, FontStyle, FontWeight, TextStyle},
+    headless::Headless,
+    input::{InputEvent, KeyBinding, KeyBindings},
+    interactive::{
+        ClickListener, DragPayload, EventListener, Interaction, KeyEvent, Message, MouseEvent,
+        MouseMoveEvent, WheelDelta, WidgetContext,
+    },
+    layout::{BoxConstraint, Geometry, LayoutContext, LayoutStats, Position, Size},
+    render::{DisplayListRecorder, PaintStats, RenderContext},
+    style::{StyleError, StyleSheet},
+    theme::{Palette, Spacing, Theme, Typography},
+    timer::TimerId,
+    widget::{Invalidation, Widget, WidgetId},
+    window::{WindowAttributes, WindowEvent, WindowHandle, WindowId},
 };
-
-const FONT_DATA: &[u8] = include_bytes!("../resources/FreeSans.ttf");
+pub use glutin::{ModifiersState, MouseButton, VirtualKeyCode};
+pub use webrender::api::ColorF;
 
 pub trait Application {
     type Message: Message;
 
     fn build(&mut self, context: &mut WidgetContext<Self::Message>) -> WidgetId;
 
+    /// Called once the first windows are open, before any event is handled.
+    fn on_start(&mut self, _context: &mut WidgetContext<Self::Message>) {}
+
+    /// Called when the user asks to close a window, with its close button or the quit key.
+    /// Returning false keeps the window open, e.g. to ask about unsaved changes first.
+    fn on_close_requested(
+        &mut self,
+        _window: WindowId,
+        _context: &mut WidgetContext<Self::Message>,
+    ) -> bool {
+        true
+    }
+
+    /// Called when every event is handled and no window has to be rendered, before waiting
+    /// for the next event. Events that render nothing don't call it again until a window was
+    /// rendered.
+    fn on_idle(&mut self, _context: &mut WidgetContext<Self::Message>) {}
+
+    /// Called once the last window is closed or `WidgetContext::quit` was called, before
+    /// `Imagine::run` returns.
+    fn on_exit(&mut self, _context: &mut WidgetContext<Self::Message>) {}
+
+    /// Called when the style sheet loaded with `Imagine::load_style_sheet` changed on disk but
+    /// could not be read or parsed again. The previous rules stay in effect.
+    fn on_style_error(&mut self, _error: StyleError, _context: &mut WidgetContext<Self::Message>) {}
+
+    fn key_bindings(&self) -> KeyBindings {
+        KeyBindings::default()
+    }
+
     fn handle_message(
         &mut self,
         _message: Self::Message,
         _context: &mut WidgetContext<Self::Message>,
     ) {
     }
+
+    /// Called when a window gains or loses focus, moves, is resized or minimized, or its DPI
+    /// changes.
+    fn window_event(
+        &mut self,
+        _window: WindowId,
+        _event: WindowEvent,
+        _context: &mut WidgetContext<Self::Message>,
+    ) {
+    }
 }
 
-pub(crate) struct MessageQueue<M: Message>(Vec<M>);
+/// Messages for the application, with the window each came from.
+pub(crate) struct MessageQueue<M: Message>(Vec<(Option<WindowId>, M)>);
 
 impl<M: Message> Default for MessageQueue<M> {
     fn default() -> MessageQueue<M> {
@@ -60,24 +131,14 @@ pub struct Imagine<'a, 'b, A: Application> {
     windows: HashMap<glutin::WindowId, RenderWindow>,
     renderers: Vec<webrender::Renderer>,
     application: A,
+    style_watcher: Option<StyleWatcher>,
 }
 
 impl<'a, 'b, A: Application> Imagine<'a, 'b, A> {
     pub fn new(application: A) -> Imagine<'a, 'b, A> {
-        let mut world = World::new();
-        world.add_resource(MessageQueue::<A::Message>(Vec::new()));
-        let mut dispatcher = DispatcherBuilder::new()
-            .with(
-                InteractionSystem::<A::Message>::default(),
-                "interaction",
-                &[],
-            )
-            .with(LayoutSystem, "layout", &["interaction"])
-            .with(RenderSystem, "render", &["interaction", "layout"])
-            .build();
-
-        dispatcher.setup(&mut world.res);
-
+        let (mut world, dispatcher) = create_world::<A::Message>();
+        world.add_resource(application.key_bindings());
+        world.add_resource(ClipboardResource(Box::new(SystemClipboard::default())));
         let events_loop = EventsLoop::new();
 
         Imagine {
@@ -87,39 +148,80 @@ impl<'a, 'b, A: Application> Imagine<'a, 'b, A> {
             windows: HashMap::new(),
             renderers: Vec::new(),
             application,
+            style_watcher: None,
         }
     }
 
-    pub fn create_window(&mut self, title: &str, size: Size) {
-        // TODO: Generate Unique PipelineIds per Window.
-        let pipeline_id = PipelineId(0, 0);
-        let window_entity = self.world.create_entity().build();
+    pub fn set_clipboard<C: Clipboard + 'static>(&mut self, clipboard: C) {
+        self.world.add_resource(ClipboardResource(Box::new(clipboard)));
+    }
+
+    /// Adds a TTF or OTF font that `TextStyle`s can select by `family`, `weight` and `style`.
+    pub fn load_font(
+        &mut self,
+        family: &str,
+        weight: FontWeight,
+        style: FontStyle,
+        data: Vec<u8>,
+    ) -> Result<FontId, rusttype::Error> {
+        self.world
+            .write_resource::<FontRegistry>()
+            .load(family, weight, style, data)
+    }
+
+    /// Replaces the theme every window is drawn with.
+    ///
+    /// Applications can also switch themes while running with `WidgetContext::set_theme`.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.world.add_resource(theme);
+        invalidate_windows(&self.world);
+    }
+
+    pub fn set_style_sheet(&mut self, style_sheet: StyleSheet) {
+        self.style_watcher = None;
+        self.world.add_resource(style_sheet);
+        invalidate_windows(&self.world);
+    }
+
+    /// Loads the style sheet at `path` and reloads it whenever the file changes on disk.
+    pub fn load_style_sheet<P: AsRef<Path>>(&mut self, path: P) -> Result<(), StyleError> {
+        let style_sheet = StyleSheet::load(&path)?;
+        self.set_style_sheet(style_sheet);
+        let events_proxy = self.events_loop.create_proxy();
+        self.style_watcher = Some(StyleWatcher::new(path.as_ref().to_path_buf(), events_proxy));
+        Ok(())
+    }
+
+    /// Opens a window whose widgets are built by `Application::build`.
+    pub fn create_window(&mut self, title: &str, size: Size) -> WindowId {
+        let application = &mut self.application;
+        self.create_window_with(title, size, |context| application.build(context))
+    }
+
+    /// Opens a window whose widgets are built by `build` rather than `Application::build`,
+    /// e.g. a tool window next to the main one.
+    pub fn create_window_with<F>(&mut self, title: &str, size: Size, build: F) -> WindowId
+    where
+        F: FnOnce(&mut WidgetContext<A::Message>) -> WidgetId,
+    {
+        let window = WindowId(self.world.create_entity().build());
+        let attributes = WindowAttributes::new(title, size);
         let render_window =
-            RenderWindow::new(title, &self.events_loop, window_entity, size, pipeline_id).unwrap();
-        let mut windows = self.world.write_storage::<WindowComponent>();
-        let entities = self.world.entities();
-        let mut widgets = self.world.write_storage::<WidgetComponent>();
-        let mut click_listeners = self.world.write_storage::<ClickListener<A::Message>>();
-        let mut context = WidgetContext::new(&entities, &mut widgets, &mut click_listeners);
-        let root = self.application.build(&mut context);
-        windows
-            .insert(
-                window_entity,
-                WindowComponent {
-                    root,
-                    layout_size: LayoutSize::zero(),
-                    dirty: true,
-                    pipeline_id,
-                    display_list_builder: None,
-                    hovered: None,
-                    clicked: None,
-                    font_instance_key: render_window.font_instance_key,
-                    font: Font::from_bytes(FONT_DATA).unwrap(),
-                },
-            )
-            .ok();
+            open_window(&self.world, &self.events_loop, window, &attributes, build);
         self.windows
             .insert(render_window.window.id(), render_window);
+        window
+    }
+
+    /// Changes the title, size, position and other attributes of a window once the
+    /// application runs.
+    pub fn window_handle(&mut self, window: WindowId) -> WindowHandle<A::Message> {
+        let requests = self
+            .world
+            .res
+            .get_mut::<WindowRequests<A::Message>>()
+            .expect("Could not find window requests");
+        WindowHandle::new(window, requests)
     }
 
     pub fn run(self) {
@@ -130,127 +232,216 @@ impl<'a, 'b, A: Application> Imagine<'a, 'b, A> {
             mut world,
             mut renderers,
             mut application,
+            style_watcher,
         } = self;
 
-        events_loop.run_forever(|event| {
-            if let glutin::Event::WindowEvent { event, window_id } = event {
-                let mut response = EventResponse::Continue;
-                if let Some(window) = windows.get_mut(&window_id) {
-                    response = window.handle_event::<A::Message>(event, &world);
+        let start = Instant::now();
+        let events_proxy = events_loop.create_proxy();
+        let mut timer_waker = TimerWaker::new(events_loop.create_proxy());
+        // Whether `on_idle` was called since the last frame.
+        let mut idle = false;
+
+        call_application(&world, |context| application.on_start(context));
+
+        loop {
+            if handle_window_requests::<A::Message>(
+                &mut world,
+                &events_loop,
+                &mut windows,
+                &mut renderers,
+            ) {
+                // Render the requested changes without waiting for an event.
+                let _ = events_proxy.wakeup();
+            }
+            if windows.is_empty() {
+                break;
+            }
+
+            events_loop.run_forever(|event| {
+                // Input reads the clock too, e.g. to count double clicks.
+                world.write_resource::<Clock>().now = start.elapsed();
+
+                // Due timers and running animations wake the loop too, so the style sheet is only
+                // reloaded when its watcher saw the file change.
+                if let glutin::Event::Awakened = event {
+                    if style_watcher.as_ref().map_or(false, StyleWatcher::take_changed) {
+                        reload_style_sheet(&world, &mut application);
+                    }
                 }
-                match response {
-                    EventResponse::Quit => {
-                        if let Some(window) = windows.remove(&window_id) {
-                            renderers.push(window.renderer);
-                        }
+
+                if let glutin::Event::WindowEvent { event, window_id } = event {
+                    let mut response = EventResponse::Continue;
+                    if let Some(window) = windows.get_mut(&window_id) {
+                        response = window.handle_event(event, &world);
                     }
-                    EventResponse::Dirty => {
-                        if let Some(window) = windows.get(&window_id) {
-                            let mut window_components = world.write_storage::<WindowComponent>();
-
-                            let window_component = window_components
-                                .get_mut(window.entity)
-                                .expect("Could not find window component");
-                            window_component.set_dirty(true);
+                    match response {
+                        EventResponse::CloseRequested => {
+                            let window = windows
+                                .get(&window_id)
+                                .map(|render_window| WindowId(render_window.entity));
+                            let close = window.is_some_and(|window| {
+                                call_application(&world, |context| {
+                                    application.on_close_requested(window, context)
+                                })
+                            });
+                            if close {
+                                if let Some(window) = windows.remove(&window_id) {
+                                    remove_window(&world, WindowId(window.entity));
+                                    renderers.push(window.renderer);
+                                }
+                            }
+                        }
+                        EventResponse::Dirty => {
+                            if let Some(window) = windows.get(&window_id) {
+                                let mut window_components =
+                                    world.write_storage::<WindowComponent>();
+
+                                let window_component = window_components
+                                    .get_mut(window.entity)
+                                    .expect("Could not find window component");
+                                window_component.set_dirty(true);
+                            }
                         }
+                        EventResponse::ToggleProfiler => {
+                            if let Some(window) = windows.get_mut(&window_id) {
+                                window.toggle_profiler();
+                            }
+                        }
+                        EventResponse::Continue => {}
                     }
-                    EventResponse::Continue => {}
                 }
-            }
 
-            for window in windows.values() {
-                let mut window_components = world.write_storage::<WindowComponent>();
-                let window_component = window_components
-                    .get_mut(window.entity)
-                    .expect("Could not find window component");
+                for window in windows.values() {
+                    let mut window_components = world.write_storage::<WindowComponent>();
+                    let window_component = window_components
+                        .get_mut(window.entity)
+                        .expect("Could not find window component");
 
-                if !window_component.dirty() {
-                    continue;
-                }
+                    if !window_component.dirty() {
+                        continue;
+                    }
 
-                let hidpi_factor = window.window.get_hidpi_factor();
+                    let hidpi_factor = window.window.get_hidpi_factor();
 
-                let framebuffer_size = {
-                    let size = window
-                        .window
-                        .get_inner_size()
-                        .unwrap()
-                        .to_physical(hidpi_factor);
-                    DeviceIntSize::new(size.width as i32, size.height as i32)
-                };
+                    let framebuffer_size = {
+                        let size = window
+                            .window
+                            .get_inner_size()
+                            .unwrap()
+                            .to_physical(hidpi_factor);
+                        DeviceIntSize::new(size.width as i32, size.height as i32)
+                    };
 
-                let layout_size: LayoutSize =
-                    framebuffer_size.to_f32() / euclid::Scale::new(hidpi_factor as f32);
+                    let layout_size: LayoutSize =
+                        framebuffer_size.to_f32() / euclid::Scale::new(hidpi_factor as f32);
 
-                window_component.layout_size = layout_size;
-            }
+                    window_component.layout_size = layout_size;
+                }
 
-            dispatcher.dispatch(&world.res);
-            world.maintain();
+                *world.write_resource::<LayoutStats>() = LayoutStats::default();
+                *world.write_resource::<PaintStats>() = PaintStats::default();
+                dispatcher.dispatch(&world.res);
+                world.maintain();
 
-            let entities = world.entities();
-            let mut widgets = world.write_storage::<WidgetComponent>();
-            let mut click_listeners = world.write_storage::<ClickListener<A::Message>>();
-            let mut context = WidgetContext::new(&entities, &mut widgets, &mut click_listeners);
+                let timers_fired = fire_timers::<A::Message>(&world);
+                if handle_messages(&world, &mut application) || timers_fired {
+                    dispatcher.dispatch(&world.res);
+                    world.maintain();
+                }
 
-            let mut message_queue = world.write_resource::<MessageQueue<A::Message>>();
-            let messages = mem::replace(&mut *message_queue, MessageQueue::default());
+                let mut window_components = world.write_storage::<WindowComponent>();
+                let mut animating = false;
+                let mut rendered = false;
 
-            for message in messages.0 {
-                application.handle_message(message, &mut context);
-            }
+                for window in windows.values_mut() {
+                    let window_component = window_components
+                        .get_mut(window.entity)
+                        .expect("Could not find window component");
+                    animating |= window_component.animating();
 
-            let mut window_components = world.write_storage::<WindowComponent>();
+                    unsafe {
+                        window.window.make_current().ok();
+                    }
 
-            for window in windows.values_mut() {
-                let window_component = window_components
-                    .get_mut(window.entity)
-                    .expect("Could not find window component");
+                    let hidpi_factor = window.window.get_hidpi_factor();
+                    let framebuffer_size = {
+                        let size = window
+                            .window
+                            .get_inner_size()
+                            .unwrap()
+                            .to_physical(hidpi_factor);
+                        DeviceIntSize::new(size.width as i32, size.height as i32)
+                    };
+
+                    if window_component.dirty() {
+                        window_component.set_dirty(false);
+                        rendered = true;
+
+                        if let Some(builder) = window_component.display_list_builder.take() {
+                            let mut txn = Transaction::new();
+
+                            for update in window_component.fonts.take_updates() {
+                                match update {
+                                    FontUpdate::AddFont(key, data) => {
+                                        txn.add_raw_font(key, data, 0)
+                                    }
+                                    FontUpdate::AddInstance(key, font_key, size) => {
+                                        txn.add_font_instance(
+                                            key,
+                                            font_key,
+                                            size,
+                                            None,
+                                            None,
+                                            Vec::new(),
+                                        )
+                                    }
+                                }
+                            }
+
+                            txn.set_display_list(
+                                window.epoch,
+                                None,
+                                window_component.layout_size,
+                                builder.finalize(),
+                                true,
+                            );
+                            txn.set_root_pipeline(window_component.pipeline_id);
+                            txn.generate_frame();
+                            window.api.send_transaction(window.document_id, txn);
+                        }
+                    }
 
-                unsafe {
-                    window.window.make_current().ok();
+                    window.renderer.update();
+                    window.renderer.render(framebuffer_size).unwrap();
+                    window.window.swap_buffers().ok();
                 }
 
-                let hidpi_factor = window.window.get_hidpi_factor();
-                let framebuffer_size = {
-                    let size = window
-                        .window
-                        .get_inner_size()
-                        .unwrap()
-                        .to_physical(hidpi_factor);
-                    DeviceIntSize::new(size.width as i32, size.height as i32)
-                };
+                drop(window_components);
 
-                if window_component.dirty() {
-                    window_component.set_dirty(false);
-
-                    if let Some(builder) = window_component.display_list_builder.take() {
-                        let mut txn = Transaction::new();
-
-                        txn.set_display_list(
-                            window.epoch,
-                            None,
-                            window_component.layout_size,
-                            builder.finalize(),
-                            true,
-                        );
-                        txn.set_root_pipeline(window_component.pipeline_id);
-                        txn.generate_frame();
-                        window.api.send_transaction(window.document_id, txn);
-                    }
+                // Swapping buffers waits for the display, which paces the frames.
+                if animating {
+                    let _ = events_proxy.wakeup();
+                } else if !rendered && !idle {
+                    call_application(&world, |context| application.on_idle(context));
                 }
+                idle = !animating && !rendered;
 
-                window.renderer.update();
-                window.renderer.render(framebuffer_size).unwrap();
-                window.window.swap_buffers().ok();
-            }
+                let next_timer = world.read_resource::<Timers<A::Message>>().next_due();
+                timer_waker.wake_at(next_timer.map(|due| start + due));
 
-            if windows.is_empty() {
-                glutin::ControlFlow::Break
-            } else {
-                glutin::ControlFlow::Continue
-            }
-        });
+                // Windows can only be created outside of the loop, so it is left to handle
+                // the requested windows and entered again.
+                let window_requested =
+                    !world.read_resource::<WindowRequests<A::Message>>().0.is_empty();
+                if windows.is_empty() || window_requested {
+                    glutin::ControlFlow::Break
+                } else {
+                    glutin::ControlFlow::Continue
+                }
+            });
+        }
+
+        call_application(&world, |context| application.on_exit(context));
 
         for renderer in renderers {
             renderer.deinit();
@@ -258,19 +449,232 @@ impl<'a, 'b, A: Application> Imagine<'a, 'b, A> {
     }
 }
 
+pub(crate) fn create_world<'a, 'b, M: Message>() -> (World, Dispatcher<'a, 'b>) {
+    let mut world = World::new();
+    world.add_resource(MessageQueue::<M>(Vec::new()));
+    world.add_resource(Timers::<M>::default());
+    world.add_resource(WindowRequests::<M>::default());
+    world.add_resource(WindowEvents::default());
+    let mut dispatcher = DispatcherBuilder::new()
+        .with(InteractionSystem::<M>::default(), "interaction", &[])
+        .with(AnimationSystem, "animation", &["interaction"])
+        .with(StyleSystem, "style", &["interaction", "animation"])
+        .with(LayoutSystem, "layout", &["interaction", "animation", "style"])
+        .with(
+            RenderSystem,
+            "render",
+            &["interaction", "animation", "style", "layout"],
+        )
+        .build();
+
+    dispatcher.setup(&mut world.res);
+
+    (world, dispatcher)
+}
+
+/// Creates the renderer of a window and builds its widgets. Every window renders its own
+/// pipeline, identified by the window's entity.
+fn open_window<M, F>(
+    world: &World,
+    events_loop: &EventsLoop,
+    window: WindowId,
+    attributes: &WindowAttributes,
+    build: F,
+) -> RenderWindow
+where
+    M: Message,
+    F: FnOnce(&mut WidgetContext<M>) -> WidgetId,
+{
+    let pipeline_id = PipelineId(0, window.0.id());
+    let clear_color = world.read_resource::<Theme>().palette.background;
+    let render_window =
+        RenderWindow::new(attributes, events_loop, window.0, clear_color).unwrap();
+    let root = with_context(world, build);
+    world
+        .write_storage::<WindowComponent>()
+        .insert(
+            window.0,
+            WindowComponent::new(root, pipeline_id, render_window.api.get_namespace_id()),
+        )
+        .ok();
+    render_window
+}
+
+/// Opens, changes and closes the windows the application asked for, returning whether it
+/// asked for any.
+fn handle_window_requests<M: Message>(
+    world: &mut World,
+    events_loop: &EventsLoop,
+    windows: &mut HashMap<glutin::WindowId, RenderWindow>,
+    renderers: &mut Vec<webrender::Renderer>,
+) -> bool {
+    let requests = mem::take(&mut world.write_resource::<WindowRequests<M>>().0);
+    if requests.is_empty() {
+        return false;
+    }
+
+    for request in requests {
+        match request {
+            WindowRequest::Open {
+                window,
+                attributes,
+                build,
+            } => {
+                let render_window = open_window(world, events_loop, window, &attributes, build);
+                windows.insert(render_window.window.id(), render_window);
+            }
+            WindowRequest::Update(window, command) => {
+                if let Some(render_window) = windows
+                    .values_mut()
+                    .find(|render_window| render_window.entity == window.0)
+                {
+                    render_window.apply(&command);
+                }
+            }
+            WindowRequest::Close(window) => {
+                let window_id = windows
+                    .iter()
+                    .find(|(_, render_window)| render_window.entity == window.0)
+                    .map(|(window_id, _)| *window_id);
+                if let Some(render_window) = window_id.and_then(|id| windows.remove(&id)) {
+                    renderers.push(render_window.renderer);
+                }
+                remove_window(world, window);
+            }
+            WindowRequest::Quit => {
+                for (_, render_window) in windows.drain() {
+                    remove_window(world, WindowId(render_window.entity));
+                    renderers.push(render_window.renderer);
+                }
+            }
+        }
+    }
+    world.maintain();
+    true
+}
+
+/// Calls into the application outside of handling messages, marking every window dirty if it
+/// changed the theme or widgets.
+pub(crate) fn call_application<M, R, F>(world: &World, f: F) -> R
+where
+    M: Message,
+    F: FnOnce(&mut WidgetContext<M>) -> R,
+{
+    let (result, theme_changed, widgets_changed) = with_context(world, |context| {
+        let result = f(context);
+        (result, context.theme_changed(), context.widgets_changed())
+    });
+    invalidate_changed(world, theme_changed, widgets_changed);
+    result
+}
+
+/// Passes window events and the queued messages to the application.
+///
+/// Returns whether the application changed the theme or any widget, in which case every
+/// window was marked dirty and has to be laid out and rendered again.
+pub(crate) fn handle_messages<A: Application>(world: &World, application: &mut A) -> bool {
+    let window_events = mem::take(&mut world.write_resource::<WindowEvents>().0);
+    let messages = mem::take(&mut world.write_resource::<MessageQueue<A::Message>>().0);
+
+    let (theme_changed, widgets_changed) = with_context(world, |context| {
+        for (window, event) in window_events {
+            context.window = Some(window);
+            application.window_event(window, event, context);
+        }
+        for (window, message) in messages {
+            context.window = window;
+            application.handle_message(message, context);
+        }
+        (context.theme_changed(), context.widgets_changed())
+    });
+
+    invalidate_changed(world, theme_changed, widgets_changed)
+}
+
+/// Lays out every widget again if the theme changed, and otherwise only marks the windows
+/// dirty so the changed widgets are laid out. Returns whether anything changed.
+fn invalidate_changed(world: &World, theme_changed: bool, widgets_changed: bool) -> bool {
+    if theme_changed {
+        invalidate_windows(world);
+    } else if widgets_changed {
+        mark_windows_dirty(world);
+    }
+    theme_changed || widgets_changed
+}
+
+/// Marks every window dirty and lays out all of their widgets again, e.g. after something all
+/// of them depend on changed.
+pub(crate) fn invalidate_windows(world: &World) {
+    for window in (&mut world.write_storage::<WindowComponent>()).join() {
+        window.invalidate_layout();
+    }
+}
+
+/// Marks every window dirty, e.g. after widgets in them changed, without laying out the
+/// widgets that did not.
+fn mark_windows_dirty(world: &World) {
+    for window in (&mut world.write_storage::<WindowComponent>()).join() {
+        window.set_dirty(true);
+    }
+}
+
+fn reload_style_sheet<A: Application>(world: &World, application: &mut A) {
+    let reloaded = world.write_resource::<StyleSheet>().reload_if_changed();
+    match reloaded {
+        Ok(true) => invalidate_windows(world),
+        Ok(false) => {}
+        Err(error) => call_application(world, |context| application.on_style_error(error, context)),
+    }
+}
+
 pub(crate) struct WindowComponent {
     root: WidgetId,
     layout_size: LayoutSize,
     dirty: bool,
+    // Whether every widget is laid out again rather than only those whose layout changed,
+    // e.g. because the theme changed.
+    relayout: bool,
+    // Whether a widget in the window changed in the last animation frame, in which case
+    // another frame is requested.
+    animating: bool,
     pipeline_id: PipelineId,
     hovered: Option<Entity>,
-    clicked: Option<Entity>,
+    captured: Option<Capture>,
+    last_click: Option<Click>,
+    drag: Option<Drag>,
+    focused: Option<Entity>,
+    // The keys held down, to tell presses the system repeats from new ones.
+    pressed_keys: Vec<VirtualKeyCode>,
+    cursor_position: Position,
     pub(crate) display_list_builder: Option<DisplayListBuilder>,
-    pub(crate) font_instance_key: FontInstanceKey,
-    pub(crate) font: Font<'static>,
+    pub(crate) fonts: FontCache,
 }
 
 impl WindowComponent {
+    pub(crate) fn new(
+        root: WidgetId,
+        pipeline_id: PipelineId,
+        font_namespace: IdNamespace,
+    ) -> WindowComponent {
+        WindowComponent {
+            root,
+            layout_size: LayoutSize::zero(),
+            dirty: true,
+            relayout: true,
+            animating: false,
+            pipeline_id,
+            display_list_builder: None,
+            hovered: None,
+            captured: None,
+            last_click: None,
+            drag: None,
+            focused: None,
+            pressed_keys: Vec::new(),
+            cursor_position: Position::zero(),
+            fonts: FontCache::new(font_namespace),
+        }
+    }
+
     pub fn layout_size(&self) -> LayoutSize {
         self.layout_size
     }
@@ -282,6 +686,16 @@ impl WindowComponent {
     pub fn set_dirty(&mut self, dirty: bool) {
         self.dirty = dirty
     }
+
+    /// Marks the window dirty and lays out all of its widgets again in the next frame.
+    pub(crate) fn invalidate_layout(&mut self) {
+        self.dirty = true;
+        self.relayout = true;
+    }
+
+    pub fn animating(&self) -> bool {
+        self.animating
+    }
 }
 
 impl Component for WindowComponent {
@@ -295,24 +709,37 @@ pub struct RenderWindow {
     epoch: Epoch,
     api: RenderApi,
     entity: Entity,
-    pipeline_id: PipelineId,
-    font_instance_key: FontInstanceKey,
     show_profiler: bool,
 }
 
 impl RenderWindow {
     pub fn new(
-        title: &str,
+        attributes: &WindowAttributes,
         events_loop: &EventsLoop,
         entity: Entity,
-        size: Size,
-        pipeline_id: PipelineId,
+        clear_color: ColorF,
     ) -> Result<RenderWindow, glutin::CreationError> {
-        let window_builder = WindowBuilder::new()
-            .with_title(title)
-            .with_dimensions((f64::from(size.width), f64::from(size.height)).into());
+        let mut window_builder = WindowBuilder::new()
+            .with_title(attributes.title.clone())
+            .with_dimensions(logical_size(attributes.size))
+            .with_resizable(attributes.resizable)
+            .with_always_on_top(attributes.always_on_top)
+            .with_decorations(attributes.decorations);
+        if let Some(min_size) = attributes.min_size {
+            window_builder = window_builder.with_min_dimensions(logical_size(min_size));
+        }
+        if let Some(max_size) = attributes.max_size {
+            window_builder = window_builder.with_max_dimensions(logical_size(max_size));
+        }
+        if attributes.fullscreen {
+            let monitor = events_loop.get_primary_monitor();
+            window_builder = window_builder.with_fullscreen(Some(monitor));
+        }
         let context = glutin::ContextBuilder::new();
         let window = glutin::GlWindow::new(window_builder, context, events_loop)?;
+        if let Some(position) = attributes.position {
+            window.set_position(logical_position(position));
+        }
 
         unsafe {
             window.make_current().ok();
@@ -332,7 +759,7 @@ impl RenderWindow {
 
         let opts = webrender::RendererOptions {
             device_pixel_ratio: hidpi_factor as f32,
-            clear_color: Some(ColorF::new(0.98, 0.98, 0.98, 1.0)),
+            clear_color: Some(clear_color),
             debug_flags: webrender::DebugFlags::empty(),
             ..webrender::RendererOptions::default()
         };
@@ -348,23 +775,6 @@ impl RenderWindow {
         let document_id = api.add_document(framebuffer_size, 0);
         let epoch = Epoch(0);
 
-        let mut txn = Transaction::new();
-
-        let font_key = api.generate_font_key();
-        txn.add_raw_font(font_key, Vec::from(FONT_DATA), 0);
-
-        let font_instance_key = api.generate_font_instance_key();
-        txn.add_font_instance(
-            font_instance_key,
-            font_key,
-            Au::from_px(32),
-            None,
-            None,
-            Vec::new(),
-        );
-
-        api.send_transaction(document_id, txn);
-
         Ok(RenderWindow {
             window,
             renderer,
@@ -372,163 +782,152 @@ impl RenderWindow {
             epoch,
             document_id,
             entity,
-            pipeline_id,
-            font_instance_key,
             show_profiler: false,
         })
     }
 
-    fn handle_event<T: 'static + Send + Sync>(
-        &mut self,
-        event: glutin::WindowEvent,
-        world: &World,
-    ) -> EventResponse {
-        let mut window_components = world.write_storage::<WindowComponent>();
-        let mut window_component = window_components.get_mut(self.entity).unwrap();
-
-        match event {
-            glutin::WindowEvent::CloseRequested
-            | glutin::WindowEvent::KeyboardInput {
-                input:
-                    glutin::KeyboardInput {
-                        virtual_keycode: Some(glutin::VirtualKeyCode::Escape),
-                        ..
-                    },
-                ..
-            } => EventResponse::Quit,
-            glutin::WindowEvent::Resized(size) => {
-                let hidpi_factor = self.window.get_hidpi_factor();
-                self.window.resize(size.to_physical(hidpi_factor));
-                let framebuffer_size = {
-                    let size = self
-                        .window
-                        .get_inner_size()
-                        .unwrap()
-                        .to_physical(hidpi_factor);
-                    DeviceIntSize::new(size.width as i32, size.height as i32)
-                };
-
-                self.api.set_document_view(
-                    self.document_id,
-                    DeviceIntRect::new(DeviceIntPoint::zero(), framebuffer_size),
-                    hidpi_factor as f32,
-                );
-                EventResponse::Dirty
+    fn handle_event(&mut self, event: glutin::WindowEvent, world: &World) -> EventResponse {
+        let window = WindowId(self.entity);
+        let report = |event| world.write_resource::<WindowEvents>().0.push((window, event));
+
+        let input = match event {
+            glutin::WindowEvent::CloseRequested => return EventResponse::CloseRequested,
+            glutin::WindowEvent::Focused(focused) => {
+                // Keys released while another window has focus are not reported.
+                if !focused {
+                    if let Some(window_component) =
+                        world.write_storage::<WindowComponent>().get_mut(self.entity)
+                    {
+                        window_component.pressed_keys.clear();
+                    }
+                }
+                report(WindowEvent::Focused(focused));
+                return EventResponse::Continue;
             }
-            glutin::WindowEvent::KeyboardInput {
-                input:
-                    glutin::KeyboardInput {
-                        state: glutin::ElementState::Pressed,
-                        virtual_keycode: Some(glutin::VirtualKeyCode::P),
-                        ..
-                    },
-                ..
-            } => {
-                if !self.show_profiler {
-                    self.renderer
-                        .set_debug_flags(webrender::DebugFlags::PROFILER_DBG);
+            glutin::WindowEvent::Moved(position) => {
+                report(WindowEvent::Moved(Position::new(position.x as f32, position.y as f32)));
+                return EventResponse::Continue;
+            }
+            glutin::WindowEvent::HiDpiFactorChanged(hidpi_factor) => {
+                report(WindowEvent::DpiChanged(hidpi_factor as f32));
+                self.update_document_view(hidpi_factor);
+                return EventResponse::Dirty;
+            }
+            glutin::WindowEvent::Resized(size) => {
+                if size.width <= 0.0 || size.height <= 0.0 {
+                    report(WindowEvent::Minimized);
                 } else {
-                    self.renderer
-                        .set_debug_flags(webrender::DebugFlags::empty());
+                    report(WindowEvent::Resized(Size::new(
+                        size.width as f32,
+                        size.height as f32,
+                    )));
                 }
-                self.show_profiler = !self.show_profiler;
-                EventResponse::Continue
+
+                self.update_document_view(self.window.get_hidpi_factor());
+                return EventResponse::Dirty;
             }
-            glutin::WindowEvent::CursorMoved { position, .. } => {
-                if window_component.clicked.is_some() {
-                    return EventResponse::Continue;
+            glutin::WindowEvent::KeyboardInput { input, .. } => {
+                let key_event =
+                    KeyEvent::new(input.virtual_keycode, input.scancode, input.modifiers);
+                match input.state {
+                    glutin::ElementState::Pressed => InputEvent::KeyDown(key_event),
+                    glutin::ElementState::Released => InputEvent::KeyUp(key_event),
                 }
-                let world_position = WorldPoint::new(position.x as f32, position.y as f32);
-                let results = self.api.hit_test(
-                    self.document_id,
-                    Some(self.pipeline_id),
-                    world_position,
-                    HitTestFlags::empty(),
-                );
-                let interactive = world.read_storage::<Interactive>();
-                let entities = world.entities();
-                let mut events = world.write_storage::<Event>();
-                let hit = results
-                    .items
-                    .iter()
-                    .map(|item| item.tag.0)
-                    .next()
-                    .and_then(|id| {
-                        (&entities, &interactive)
-                            .join()
-                            .filter(|(e, _)| entities.is_alive(*e))
-                            .find(|(_, i)| i.tag == id)
-                            .map(|(e, _)| e)
-                    });
-
-                let changed = hit != window_component.hovered;
-
-                if changed {
-                    match (hit, window_component.hovered) {
-                        (Some(new), Some(old)) => {
-                            events
-                                .insert(old, Event::new(Interaction::Hovered(false)))
-                                .ok();
-                            events
-                                .insert(new, Event::new(Interaction::Hovered(true)))
-                                .ok();
-                        }
-                        (Some(new), None) => {
-                            events
-                                .insert(new, Event::new(Interaction::Hovered(true)))
-                                .ok();
-                        }
-                        (None, Some(old)) => {
-                            events
-                                .insert(old, Event::new(Interaction::Hovered(false)))
-                                .ok();
-                        }
-                        _ => {}
-                    }
+            }
+            glutin::WindowEvent::ReceivedCharacter(character) => InputEvent::Character(character),
+            glutin::WindowEvent::MouseWheel { delta, .. } => match delta {
+                glutin::MouseScrollDelta::LineDelta(x, y) => {
+                    InputEvent::Wheel(WheelDelta::Lines(x, y))
                 }
+                glutin::MouseScrollDelta::PixelDelta(position) => InputEvent::Wheel(
+                    WheelDelta::Pixels(position.x as f32, position.y as f32),
+                ),
+            },
+            glutin::WindowEvent::CursorMoved { position, .. } => {
+                InputEvent::CursorMoved(Position::new(position.x as f32, position.y as f32))
+            }
+            glutin::WindowEvent::MouseInput { button, state, .. } => match state {
+                glutin::ElementState::Pressed => InputEvent::MouseDown(button),
+                glutin::ElementState::Released => InputEvent::MouseUp(button),
+            },
+            _ => return EventResponse::Continue,
+        };
 
-                window_component.hovered = hit;
+        input::handle_input(world, self.entity, input)
+    }
 
-                if changed {
-                    EventResponse::Dirty
+    fn apply(&mut self, command: &WindowCommand) {
+        match command {
+            WindowCommand::SetTitle(title) => self.window.set_title(title),
+            WindowCommand::SetSize(size) => self.window.set_inner_size(logical_size(*size)),
+            WindowCommand::SetMinSize(size) => {
+                self.window.set_min_dimensions(size.map(logical_size))
+            }
+            WindowCommand::SetMaxSize(size) => {
+                self.window.set_max_dimensions(size.map(logical_size))
+            }
+            WindowCommand::SetResizable(resizable) => self.window.set_resizable(*resizable),
+            WindowCommand::SetFullscreen(fullscreen) => {
+                let monitor = if *fullscreen {
+                    Some(self.window.get_current_monitor())
                 } else {
-                    EventResponse::Continue
-                }
+                    None
+                };
+                self.window.set_fullscreen(monitor);
             }
-            glutin::WindowEvent::MouseInput {
-                button: glutin::MouseButton::Left,
-                state,
-                ..
-            } => {
-                match state {
-                    glutin::ElementState::Pressed => {
-                        if let Some(entity) = window_component.hovered {
-                            let mut events = world.write_storage::<Event>();
-                            events
-                                .insert(entity, Event::new(Interaction::MouseDown))
-                                .ok();
-                            window_component.clicked = Some(entity);
-                        }
-                    }
-                    glutin::ElementState::Released => {
-                        if let Some(entity) = window_component.clicked.take() {
-                            let mut events = world.write_storage::<Event>();
-                            events.insert(entity, Event::new(Interaction::MouseUp)).ok();
-                        }
-                    }
-                }
-
-                EventResponse::Dirty
+            WindowCommand::SetAlwaysOnTop(always_on_top) => {
+                self.window.set_always_on_top(*always_on_top)
+            }
+            WindowCommand::SetDecorations(decorations) => {
+                self.window.set_decorations(*decorations)
+            }
+            WindowCommand::SetPosition(position) => {
+                self.window.set_position(logical_position(*position))
             }
-            _ => EventResponse::Continue,
         }
     }
+
+    /// Resizes the GL context and the document to the window's size in device pixels.
+    fn update_document_view(&mut self, hidpi_factor: f64) {
+        let size = self
+            .window
+            .get_inner_size()
+            .unwrap()
+            .to_physical(hidpi_factor);
+        self.window.resize(size);
+        let framebuffer_size = DeviceIntSize::new(size.width as i32, size.height as i32);
+        self.api.set_document_view(
+            self.document_id,
+            DeviceIntRect::new(DeviceIntPoint::zero(), framebuffer_size),
+            hidpi_factor as f32,
+        );
+    }
+
+    fn toggle_profiler(&mut self) {
+        if !self.show_profiler {
+            self.renderer
+                .set_debug_flags(webrender::DebugFlags::PROFILER_DBG);
+        } else {
+            self.renderer
+                .set_debug_flags(webrender::DebugFlags::empty());
+        }
+        self.show_profiler = !self.show_profiler;
+    }
+}
+
+fn logical_size(size: Size) -> glutin::dpi::LogicalSize {
+    glutin::dpi::LogicalSize::new(f64::from(size.width), f64::from(size.height))
+}
+
+fn logical_position(position: Position) -> glutin::dpi::LogicalPosition {
+    glutin::dpi::LogicalPosition::new(f64::from(position.x), f64::from(position.y))
 }
 
-enum EventResponse {
+pub(crate) enum EventResponse {
     Continue,
-    Quit,
+    CloseRequested,
     Dirty,
+    ToggleProfiler,
 }
 
 struct Notifier {
@@ -1,7 +1,378 @@
-// pub use webrender::api::*;
+use crate::Geometry;
+use specs::{Component, DenseVecStorage};
+use webrender::api::units::*;
+use webrender::api::*;
 
-// pub struct BaseDisplayItem {
-//     bounds: LayoutRect,
-//     clip_rect: LayoutRect,
-//     clipping:
-// }
+/// A display list operation recorded while a widget rendered.
+enum DisplayItem {
+    Clip {
+        parent: SpaceAndClipInfo,
+        clip_rect: LayoutRect,
+        complex_clips: Vec<ComplexClipRegion>,
+        image_mask: Option<ImageMask>,
+        // The id the clip had when it was recorded, which items recorded after it refer to.
+        id: ClipId,
+    },
+    Rect {
+        common: CommonItemProperties,
+        color: ColorF,
+    },
+    BoxShadow {
+        common: CommonItemProperties,
+        box_bounds: LayoutRect,
+        offset: LayoutVector2D,
+        color: ColorF,
+        blur_radius: f32,
+        spread_radius: f32,
+        border_radius: BorderRadius,
+        clip_mode: BoxShadowClipMode,
+    },
+    Line {
+        common: CommonItemProperties,
+        area: LayoutRect,
+        wavy_line_thickness: f32,
+        orientation: LineOrientation,
+        color: ColorF,
+        style: LineStyle,
+    },
+    Text {
+        common: CommonItemProperties,
+        bounds: LayoutRect,
+        glyphs: Vec<GlyphInstance>,
+        font_key: FontInstanceKey,
+        color: ColorF,
+        glyph_options: Option<GlyphOptions>,
+    },
+    Shadow {
+        space_and_clip: SpaceAndClipInfo,
+        shadow: Shadow,
+        should_inflate: bool,
+    },
+    PopAllShadows,
+}
+
+/// Pushes items to a window's display list like `DisplayListBuilder`, with the methods widgets
+/// render with, and records them so they can be replayed in later frames.
+pub struct DisplayListRecorder<'a> {
+    builder: &'a mut DisplayListBuilder,
+    items: Vec<DisplayItem>,
+}
+
+impl<'a> DisplayListRecorder<'a> {
+    pub(crate) fn new(builder: &'a mut DisplayListBuilder) -> DisplayListRecorder<'a> {
+        DisplayListRecorder {
+            builder,
+            items: Vec::new(),
+        }
+    }
+
+    pub fn define_clip<I>(
+        &mut self,
+        parent: &SpaceAndClipInfo,
+        clip_rect: LayoutRect,
+        complex_clips: I,
+        image_mask: Option<ImageMask>,
+    ) -> ClipId
+    where
+        I: IntoIterator<Item = ComplexClipRegion>,
+    {
+        let complex_clips = complex_clips.into_iter().collect::<Vec<_>>();
+        let id = self.builder.define_clip(parent, clip_rect, complex_clips.clone(), image_mask);
+        self.items.push(DisplayItem::Clip {
+            parent: *parent,
+            clip_rect,
+            complex_clips,
+            image_mask,
+            id,
+        });
+        id
+    }
+
+    pub fn push_rect(&mut self, common: &CommonItemProperties, color: ColorF) {
+        self.builder.push_rect(common, color);
+        self.items.push(DisplayItem::Rect {
+            common: *common,
+            color,
+        });
+    }
+
+    // Takes the same arguments as `DisplayListBuilder::push_box_shadow`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn push_box_shadow(
+        &mut self,
+        common: &CommonItemProperties,
+        box_bounds: LayoutRect,
+        offset: LayoutVector2D,
+        color: ColorF,
+        blur_radius: f32,
+        spread_radius: f32,
+        border_radius: BorderRadius,
+        clip_mode: BoxShadowClipMode,
+    ) {
+        self.builder.push_box_shadow(
+            common,
+            box_bounds,
+            offset,
+            color,
+            blur_radius,
+            spread_radius,
+            border_radius,
+            clip_mode,
+        );
+        self.items.push(DisplayItem::BoxShadow {
+            common: *common,
+            box_bounds,
+            offset,
+            color,
+            blur_radius,
+            spread_radius,
+            border_radius,
+            clip_mode,
+        });
+    }
+
+    pub fn push_line(
+        &mut self,
+        common: &CommonItemProperties,
+        area: &LayoutRect,
+        wavy_line_thickness: f32,
+        orientation: LineOrientation,
+        color: &ColorF,
+        style: LineStyle,
+    ) {
+        self.builder.push_line(common, area, wavy_line_thickness, orientation, color, style);
+        self.items.push(DisplayItem::Line {
+            common: *common,
+            area: *area,
+            wavy_line_thickness,
+            orientation,
+            color: *color,
+            style,
+        });
+    }
+
+    pub fn push_text(
+        &mut self,
+        common: &CommonItemProperties,
+        bounds: LayoutRect,
+        glyphs: &[GlyphInstance],
+        font_key: FontInstanceKey,
+        color: ColorF,
+        glyph_options: Option<GlyphOptions>,
+    ) {
+        self.builder.push_text(common, bounds, glyphs, font_key, color, glyph_options);
+        self.items.push(DisplayItem::Text {
+            common: *common,
+            bounds,
+            glyphs: glyphs.to_vec(),
+            font_key,
+            color,
+            glyph_options,
+        });
+    }
+
+    pub fn push_shadow(
+        &mut self,
+        space_and_clip: &SpaceAndClipInfo,
+        shadow: Shadow,
+        should_inflate: bool,
+    ) {
+        self.builder.push_shadow(space_and_clip, shadow, should_inflate);
+        self.items.push(DisplayItem::Shadow {
+            space_and_clip: *space_and_clip,
+            shadow,
+            should_inflate,
+        });
+    }
+
+    pub fn pop_all_shadows(&mut self) {
+        self.builder.pop_all_shadows();
+        self.items.push(DisplayItem::PopAllShadows);
+    }
+
+    /// The items recorded since the last call.
+    fn take_items(&mut self) -> Vec<DisplayItem> {
+        std::mem::take(&mut self.items)
+    }
+}
+
+/// The ids clips were recorded with and the ids they got when they were replayed.
+#[derive(Default)]
+struct ClipMap(Vec<(ClipId, ClipId)>);
+
+impl ClipMap {
+    fn get(&self, id: ClipId) -> ClipId {
+        self.0
+            .iter()
+            .find(|(recorded, _)| *recorded == id)
+            .map(|(_, replayed)| *replayed)
+            .unwrap_or(id)
+    }
+
+    fn space_and_clip(&self, space_and_clip: &SpaceAndClipInfo) -> SpaceAndClipInfo {
+        SpaceAndClipInfo {
+            spatial_id: space_and_clip.spatial_id,
+            clip_id: self.get(space_and_clip.clip_id),
+        }
+    }
+
+    fn common(&self, common: &CommonItemProperties) -> CommonItemProperties {
+        CommonItemProperties {
+            clip_id: self.get(common.clip_id),
+            ..*common
+        }
+    }
+}
+
+/// Pushes a recorded item to `builder`, replacing the ids of the clips in `clips`. Returns the
+/// new id of a clip.
+fn push_item(
+    builder: &mut DisplayListBuilder,
+    item: &DisplayItem,
+    clips: &ClipMap,
+) -> Option<ClipId> {
+    match item {
+        DisplayItem::Clip {
+            parent,
+            clip_rect,
+            complex_clips,
+            image_mask,
+            ..
+        } => {
+            return Some(builder.define_clip(
+                &clips.space_and_clip(parent),
+                *clip_rect,
+                complex_clips.clone(),
+                *image_mask,
+            ));
+        }
+        DisplayItem::Rect { common, color } => builder.push_rect(&clips.common(common), *color),
+        DisplayItem::BoxShadow {
+            common,
+            box_bounds,
+            offset,
+            color,
+            blur_radius,
+            spread_radius,
+            border_radius,
+            clip_mode,
+        } => builder.push_box_shadow(
+            &clips.common(common),
+            *box_bounds,
+            *offset,
+            *color,
+            *blur_radius,
+            *spread_radius,
+            *border_radius,
+            *clip_mode,
+        ),
+        DisplayItem::Line {
+            common,
+            area,
+            wavy_line_thickness,
+            orientation,
+            color,
+            style,
+        } => builder.push_line(
+            &clips.common(common),
+            area,
+            *wavy_line_thickness,
+            *orientation,
+            color,
+            *style,
+        ),
+        DisplayItem::Text {
+            common,
+            bounds,
+            glyphs,
+            font_key,
+            color,
+            glyph_options,
+        } => builder.push_text(
+            &clips.common(common),
+            *bounds,
+            glyphs,
+            *font_key,
+            *color,
+            *glyph_options,
+        ),
+        DisplayItem::Shadow {
+            space_and_clip,
+            shadow,
+            should_inflate,
+        } => builder.push_shadow(&clips.space_and_clip(space_and_clip), *shadow, *should_inflate),
+        DisplayItem::PopAllShadows => builder.pop_all_shadows(),
+    }
+    None
+}
+
+/// The display items a widget recorded when it last rendered, along with what its rendering
+/// depended on besides the widget itself.
+pub(crate) struct PaintCache {
+    geometry: Geometry,
+    // The space and clip the widget was rendered in and the one it left for its children.
+    space_and_clip: SpaceAndClipInfo,
+    children_space_and_clip: SpaceAndClipInfo,
+    // The first hit test tag the widget took and how many it took.
+    first_tag: u64,
+    tags: u64,
+    items: Vec<DisplayItem>,
+}
+
+impl Component for PaintCache {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl PaintCache {
+    /// Takes the items `recorder` recorded while a widget rendered.
+    pub(crate) fn record(
+        recorder: &mut DisplayListRecorder,
+        geometry: Geometry,
+        space_and_clip: SpaceAndClipInfo,
+        children_space_and_clip: SpaceAndClipInfo,
+        first_tag: u64,
+        next_tag: u64,
+    ) -> PaintCache {
+        PaintCache {
+            geometry,
+            space_and_clip,
+            children_space_and_clip,
+            first_tag,
+            tags: next_tag - first_tag,
+            items: recorder.take_items(),
+        }
+    }
+
+    /// Whether replaying the items draws what rendering the widget would, given that it was
+    /// not invalidated.
+    pub(crate) fn is_valid(
+        &self,
+        geometry: Geometry,
+        space_and_clip: SpaceAndClipInfo,
+        first_tag: u64,
+    ) -> bool {
+        self.geometry == geometry
+            && self.space_and_clip == space_and_clip
+            && self.first_tag == first_tag
+    }
+
+    /// How many hit test tags rendering the widget took.
+    pub(crate) fn tags(&self) -> u64 {
+        self.tags
+    }
+
+    /// Pushes the recorded items to the display list `recorder` builds, without recording
+    /// them again. Returns the space and clip the widget left for its children.
+    pub(crate) fn replay(&self, recorder: &mut DisplayListRecorder) -> SpaceAndClipInfo {
+        let mut clips = ClipMap::default();
+        for item in &self.items {
+            let replayed = push_item(recorder.builder, item, &clips);
+            // Clips are numbered in the order they are defined, which can differ from the
+            // frame the items were recorded in.
+            if let (DisplayItem::Clip { id, .. }, Some(replayed)) = (item, replayed) {
+                clips.0.push((*id, replayed));
+            }
+        }
+        clips.space_and_clip(&self.children_space_and_clip)
+    }
+}
@@ -1,33 +1,96 @@
+mod display_list;
+
+pub use self::display_list::DisplayListRecorder;
+pub(crate) use self::display_list::PaintCache;
+
+use crate::{
+    font::{FontCache, FontInstance, FontRegistry},
+    Theme,
+};
+use specs::{Component, NullStorage};
 use webrender::api::{DisplayListBuilder, FontInstanceKey, SpaceAndClipInfo, PipelineId};
 
+/// Marks a widget whose appearance changed, e.g. because it was hovered, so it is rendered
+/// again instead of replaying the display items it recorded in an earlier frame.
+#[derive(Default)]
+pub(crate) struct NeedsPaint;
+
+impl Component for NeedsPaint {
+    type Storage = NullStorage<Self>;
+}
+
+/// How much work rendering did since the counters were last reset, which happens at the start
+/// of every frame.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PaintStats {
+    /// Widgets whose `Widget::render` was called.
+    pub painted: usize,
+    /// Widgets whose display items from an earlier frame were replayed.
+    pub reused: usize,
+}
+
 pub struct RenderContext<'a> {
-    pub builder: &'a mut DisplayListBuilder,
+    pub builder: DisplayListRecorder<'a>,
     pub current_space_and_clip: SpaceAndClipInfo,
     next_tag_identifier: u64,
-    font_instance_key: FontInstanceKey,
+    fonts: &'a mut FontCache,
+    font_registry: &'a FontRegistry,
+    theme: &'a Theme,
+    // Themes of the ancestors of the widget being rendered that override `theme`.
+    theme_overrides: Vec<&'a Theme>,
 }
 
 impl<'a> RenderContext<'a> {
     pub(crate) fn new(
         builder: &'a mut DisplayListBuilder,
-        font_instance_key: FontInstanceKey,
+        fonts: &'a mut FontCache,
+        font_registry: &'a FontRegistry,
+        theme: &'a Theme,
         pipeline_id: PipelineId,
     ) -> RenderContext<'a> {
         RenderContext {
-            builder,
+            builder: DisplayListRecorder::new(builder),
             next_tag_identifier: 0,
-            font_instance_key,
+            fonts,
+            font_registry,
+            theme,
+            theme_overrides: Vec::new(),
             current_space_and_clip: SpaceAndClipInfo::root_scroll(pipeline_id),
         }
     }
 
+    /// The theme of the widget being rendered.
+    pub fn theme(&self) -> &Theme {
+        self.theme_overrides.last().copied().unwrap_or(self.theme)
+    }
+
+    pub(crate) fn push_theme(&mut self, theme: &'a Theme) {
+        self.theme_overrides.push(theme);
+    }
+
+    pub(crate) fn pop_theme(&mut self) {
+        self.theme_overrides.pop();
+    }
+
     pub fn next_tag_identifier(&mut self) -> u64 {
         let identifier = self.next_tag_identifier;
         self.next_tag_identifier += 1;
         identifier
     }
 
-    pub fn font_instance_key(&self) -> FontInstanceKey {
-        self.font_instance_key
+    /// The tag `next_tag_identifier` returns next, without taking it.
+    pub(crate) fn peek_tag_identifier(&self) -> u64 {
+        self.next_tag_identifier
+    }
+
+    /// Takes `count` tags without returning them, as replaying a widget's display items does
+    /// for the tags the widget took when it rendered.
+    pub(crate) fn skip_tag_identifiers(&mut self, count: u64) {
+        self.next_tag_identifier += count;
+    }
+
+    /// The key for rendering glyphs from `font`, which is added to the window on first use.
+    pub fn font_instance_key(&mut self, font: FontInstance) -> FontInstanceKey {
+        self.fonts.instance_key(self.font_registry, font)
     }
 }
@@ -0,0 +1,269 @@
+use crate::font::{scale, FontId, FontRegistry};
+use app_units::Au;
+use rusttype::Font;
+use std::ops::Range;
+use unicode_bidi::BidiInfo;
+
+/// A glyph produced by shaping, in logical order.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct ShapedGlyph {
+    pub(crate) id: u32,
+    /// The index of the first character this glyph was produced for, counted from the start of
+    /// the whole text.
+    pub(crate) cluster: usize,
+    pub(crate) advance: f32,
+    pub(crate) x_offset: f32,
+    pub(crate) y_offset: f32,
+    pub(crate) whitespace: bool,
+    /// The bidi embedding level, odd for right-to-left text.
+    pub(crate) level: u8,
+    pub(crate) font: FontId,
+    /// The index of the span the glyph belongs to.
+    pub(crate) span: usize,
+}
+
+/// The fonts of one span of rich text.
+pub(crate) struct SpanFonts {
+    /// The span's byte range within the whole text.
+    pub(crate) range: Range<usize>,
+    pub(crate) chain: Vec<FontId>,
+    pub(crate) size: Au,
+}
+
+/// A paragraph, i.e. text without newlines, shaped into glyphs.
+pub(crate) struct ShapedParagraph {
+    pub(crate) glyphs: Vec<ShapedGlyph>,
+    pub(crate) level: u8,
+}
+
+/// Shapes the paragraph at `range` in `text`, splitting it into runs that share a direction,
+/// a span and a font first.
+///
+/// Every character uses the first font in its span's chain that has a glyph for it.
+/// `first_character` is the index of the paragraph's first character within the whole text,
+/// which is used to number the glyphs' clusters.
+pub(crate) fn shape_paragraph(
+    registry: &FontRegistry,
+    spans: &[SpanFonts],
+    text: &str,
+    range: Range<usize>,
+    first_character: usize,
+) -> ShapedParagraph {
+    let start = range.start;
+    let text = &text[range];
+    let bidi_info = BidiInfo::new(text, None);
+    let level = bidi_info
+        .paragraphs
+        .first()
+        .map_or(0, |paragraph| paragraph.level.number());
+
+    // The character index of every byte offset that starts a character.
+    let mut characters = vec![0; text.len() + 1];
+    for (index, (offset, _)) in text.char_indices().enumerate() {
+        characters[offset] = first_character + index;
+    }
+
+    let mut glyphs = Vec::new();
+    for (run, span, font_id) in runs(registry, spans, start, &bidi_info) {
+        let run_level = bidi_info.levels[run.start].number();
+        let size = spans[span].size;
+        let font = registry.font(font_id);
+        let run_glyphs = match registry.shaper(font_id) {
+            Some(face) => {
+                let face = ShapingFace {
+                    id: font_id,
+                    span,
+                    face,
+                    font,
+                };
+                shape_run(face, size, text, run.clone(), run_level, &characters)
+            }
+            // The shaper couldn't read the font, so map every character to a glyph instead.
+            None => text[run.clone()]
+                .char_indices()
+                .map(|(offset, character)| {
+                    let glyph = font.glyph(character).scaled(scale(font, size));
+                    ShapedGlyph {
+                        id: glyph.id().0,
+                        cluster: characters[run.start + offset],
+                        advance: glyph.h_metrics().advance_width,
+                        x_offset: 0.0,
+                        y_offset: 0.0,
+                        whitespace: character.is_whitespace(),
+                        level: run_level,
+                        font: font_id,
+                        span,
+                    }
+                })
+                .collect(),
+        };
+        glyphs.extend(run_glyphs);
+    }
+
+    ShapedParagraph { glyphs, level }
+}
+
+#[derive(Copy, Clone)]
+struct ShapingFace<'a> {
+    id: FontId,
+    span: usize,
+    face: &'a rustybuzz::Face<'a>,
+    font: &'a Font<'static>,
+}
+
+fn shape_run(
+    face: ShapingFace,
+    size: Au,
+    text: &str,
+    run: Range<usize>,
+    level: u8,
+    characters: &[usize],
+) -> Vec<ShapedGlyph> {
+    // Shaping positions are in font units, webrender sizes fonts by their em square.
+    let units = size.to_f32_px() / f32::from(face.font.units_per_em());
+
+    let mut buffer = rustybuzz::UnicodeBuffer::new();
+    buffer.push_str(&text[run.clone()]);
+    buffer.set_direction(if level % 2 == 1 {
+        rustybuzz::Direction::RightToLeft
+    } else {
+        rustybuzz::Direction::LeftToRight
+    });
+    buffer.guess_segment_properties();
+
+    let shaped = rustybuzz::shape(face.face, &[], buffer);
+    let mut glyphs = shaped
+        .glyph_infos()
+        .iter()
+        .zip(shaped.glyph_positions())
+        .map(|(info, position)| {
+            let offset = run.start + info.cluster as usize;
+            ShapedGlyph {
+                id: info.glyph_id,
+                cluster: characters[offset],
+                advance: position.x_advance as f32 * units,
+                x_offset: position.x_offset as f32 * units,
+                y_offset: position.y_offset as f32 * units,
+                whitespace: text[offset..]
+                    .chars()
+                    .next()
+                    .is_some_and(char::is_whitespace),
+                level,
+                font: face.id,
+                span: face.span,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // Right-to-left runs come back in visual order. Lines are broken in logical order and
+    // reordered afterwards.
+    if level % 2 == 1 {
+        glyphs.reverse();
+    }
+    glyphs
+}
+
+/// Splits the paragraph into byte ranges that share an embedding level, a span and a font.
+///
+/// `start` is the paragraph's byte offset within the whole text, which the spans' ranges are
+/// relative to.
+fn runs(
+    registry: &FontRegistry,
+    spans: &[SpanFonts],
+    start: usize,
+    bidi_info: &BidiInfo,
+) -> Vec<(Range<usize>, usize, FontId)> {
+    let mut runs = Vec::new();
+    let mut current: Option<(usize, usize, FontId)> = None;
+    for (offset, character) in bidi_info.text.char_indices() {
+        let span = spans
+            .iter()
+            .position(|span| span.range.contains(&(start + offset)))
+            .unwrap_or(0);
+        let font = match current {
+            // Marks, joiners and variation selectors have to be shaped with the character
+            // they modify, as do spaces to avoid needless runs.
+            Some((_, previous_span, previous))
+                if previous_span == span
+                    && (character.is_whitespace() || continues_cluster(character)) =>
+            {
+                previous
+            }
+            _ => {
+                let chain = &spans[span].chain;
+                chain
+                    .iter()
+                    .copied()
+                    .find(|font| registry.has_glyph(*font, character))
+                    .unwrap_or(chain[0])
+            }
+        };
+
+        match current {
+            Some((run_start, previous_span, previous))
+                if previous_span == span
+                    && previous == font
+                    && bidi_info.levels[run_start] == bidi_info.levels[offset] => {}
+            Some((run_start, previous_span, previous)) => {
+                runs.push((run_start..offset, previous_span, previous));
+                current = Some((offset, span, font));
+            }
+            None => current = Some((offset, span, font)),
+        }
+    }
+    if let Some((run_start, span, font)) = current {
+        runs.push((run_start..bidi_info.text.len(), span, font));
+    }
+    runs
+}
+
+/// Whether `character` modifies the one before it rather than standing on its own.
+fn continues_cluster(character: char) -> bool {
+    matches!(
+        character,
+        '\u{0300}'..='\u{036F}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{200C}'..='\u{200D}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{FE20}'..='\u{FE2F}'
+            | '\u{1F3FB}'..='\u{1F3FF}'
+            | '\u{E0020}'..='\u{E007F}'
+            | '\u{E0100}'..='\u{E01EF}'
+    )
+}
+
+/// Reorders a line of glyphs from logical to visual order, reversing every sequence at an odd
+/// level from the highest level down, as in rule L2 of the Unicode bidi algorithm.
+pub(crate) fn reorder(glyphs: &mut [ShapedGlyph]) {
+    let highest = match glyphs.iter().map(|glyph| glyph.level).max() {
+        Some(highest) => highest,
+        None => return,
+    };
+    let lowest_odd = match glyphs
+        .iter()
+        .map(|glyph| glyph.level)
+        .filter(|level| level % 2 == 1)
+        .min()
+    {
+        Some(lowest_odd) => lowest_odd,
+        None => return,
+    };
+
+    for level in (lowest_odd..=highest).rev() {
+        let mut start = 0;
+        while start < glyphs.len() {
+            if glyphs[start].level >= level {
+                let length = glyphs[start..]
+                    .iter()
+                    .take_while(|glyph| glyph.level >= level)
+                    .count();
+                glyphs[start..start + length].reverse();
+                start += length;
+            } else {
+                start += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,672 @@
+use crate::{
+    font::{FontStyle, FontWeight},
+    Theme,
+};
+use specs::{Component, DenseVecStorage};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+use webrender::api::ColorF;
+
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug)]
+pub enum StyleError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for StyleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StyleError::Io(error) => write!(f, "could not read style sheet: {}", error),
+            StyleError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for StyleError {}
+
+impl From<io::Error> for StyleError {
+    fn from(error: io::Error) -> StyleError {
+        StyleError::Io(error)
+    }
+}
+
+/// Matches widgets by type, e.g. `Button`, by class, e.g. `.primary`, or by both, e.g.
+/// `Button.primary`. `*` matches every widget.
+#[derive(Clone, Debug, PartialEq)]
+struct Selector {
+    widget_type: Option<String>,
+    classes: Vec<String>,
+}
+
+impl Selector {
+    fn specificity(&self) -> usize {
+        usize::from(self.widget_type.is_some()) + 10 * self.classes.len()
+    }
+
+    fn matches(&self, widget_type: &str, classes: &[String]) -> bool {
+        self.widget_type
+            .as_ref()
+            .map_or(true, |selected| selected == widget_type)
+            && self.classes.iter().all(|class| classes.contains(class))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Declaration {
+    Color(ColorF),
+    Background(ColorF),
+    SurfaceColor(ColorF),
+    AccentColor(ColorF),
+    BorderColor(ColorF),
+    FontFamily(Vec<String>),
+    FontSize(f32),
+    FontWeight(FontWeight),
+    FontStyle(FontStyle),
+    Padding(f32),
+    PaddingSmall(f32),
+    BorderRadius(f32),
+}
+
+impl Declaration {
+    fn apply(&self, theme: &mut Theme) {
+        match self {
+            Declaration::Color(color) => theme.palette.text = *color,
+            Declaration::Background(color) => theme.palette.primary = *color,
+            Declaration::SurfaceColor(color) => theme.palette.surface = *color,
+            Declaration::AccentColor(color) => theme.palette.accent = *color,
+            Declaration::BorderColor(color) => theme.palette.border = *color,
+            Declaration::FontFamily(families) => {
+                theme.typography.body.family = families[0].clone();
+                theme.typography.body.fallback = families[1..].to_vec();
+            }
+            Declaration::FontSize(size) => theme.typography.body.size = *size,
+            Declaration::FontWeight(weight) => theme.typography.body.weight = *weight,
+            Declaration::FontStyle(style) => theme.typography.body.style = *style,
+            Declaration::Padding(padding) => theme.spacing.medium = *padding,
+            Declaration::PaddingSmall(padding) => theme.spacing.small = *padding,
+            Declaration::BorderRadius(radius) => theme.corner_radius = *radius,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Rule {
+    selectors: Vec<Selector>,
+    declarations: Vec<Declaration>,
+}
+
+#[derive(Clone, Debug)]
+struct Source {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+/// Style rules written in a subset of CSS, which change how matching widgets look without
+/// recompiling.
+///
+/// ```css
+/// /* Every button, and text boxes with the "search" class. */
+/// Button, TextBox.search {
+///     background: #3366cc;
+///     color: white;
+///     font-size: 24px;
+///     border-radius: 8px;
+/// }
+/// ```
+///
+/// A widget matches by the name of its type and by the classes added to it with
+/// `WidgetContext::add_class`. The declarations of every matching rule change the widget's
+/// theme, with more specific selectors winning and later rules winning ties, so they are
+/// inherited by the widget's descendants.
+///
+/// Each supported property sets one field of the theme: `color` the text color, `background`
+/// the primary color that fills buttons and boxes, `surface-color` the fill of text boxes,
+/// `accent-color` and `border-color`, `font-family`, `font-size`, `font-weight` and
+/// `font-style` the body text style, `padding` the medium spacing buttons are padded with,
+/// `padding-small` the small spacing text boxes are padded with, and `border-radius` the
+/// corner radius.
+#[derive(Clone, Debug, Default)]
+pub struct StyleSheet {
+    rules: Vec<Rule>,
+    source: Option<Source>,
+}
+
+impl StyleSheet {
+    pub fn parse(text: &str) -> Result<StyleSheet, StyleError> {
+        Ok(StyleSheet {
+            rules: parse_rules(text)?,
+            source: None,
+        })
+    }
+
+    /// Reads and parses the style sheet at `path`, remembering the path so the sheet can be
+    /// reloaded when the file changes.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<StyleSheet, StyleError> {
+        let path = path.as_ref();
+        let modified = modified(path);
+        let text = fs::read_to_string(path)?;
+        Ok(StyleSheet {
+            rules: parse_rules(&text)?,
+            source: Some(Source {
+                path: path.to_path_buf(),
+                modified,
+            }),
+        })
+    }
+
+    /// The file the sheet was loaded from.
+    pub fn path(&self) -> Option<&Path> {
+        self.source.as_ref().map(|source| source.path.as_path())
+    }
+
+    /// Parses the sheet's file again if it was modified since it was last read, returning
+    /// whether the rules changed.
+    ///
+    /// If the file no longer parses the current rules are kept.
+    pub(crate) fn reload_if_changed(&mut self) -> Result<bool, StyleError> {
+        let source = match &mut self.source {
+            Some(source) => source,
+            None => return Ok(false),
+        };
+        let modified = modified(&source.path);
+        if modified == source.modified {
+            return Ok(false);
+        }
+        // Errors are reported once per change rather than on every check.
+        source.modified = modified;
+
+        let rules = parse_rules(&fs::read_to_string(&source.path)?)?;
+        let changed = rules != self.rules;
+        self.rules = rules;
+        Ok(changed)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The theme for a widget of `widget_type` with `classes`, or `None` if no rule matches.
+    pub(crate) fn style(
+        &self,
+        theme: &Theme,
+        widget_type: &str,
+        classes: &[String],
+    ) -> Option<Theme> {
+        let mut matches = self
+            .rules
+            .iter()
+            .filter_map(|rule| {
+                rule.selectors
+                    .iter()
+                    .filter(|selector| selector.matches(widget_type, classes))
+                    .map(Selector::specificity)
+                    .max()
+                    .map(|specificity| (specificity, rule))
+            })
+            .collect::<Vec<_>>();
+        if matches.is_empty() {
+            return None;
+        }
+
+        // The sort is stable, so rules of the same specificity stay in source order.
+        matches.sort_by_key(|(specificity, _)| *specificity);
+        let mut theme = theme.clone();
+        for (_, rule) in matches {
+            for declaration in &rule.declarations {
+                declaration.apply(&mut theme);
+            }
+        }
+        Some(theme)
+    }
+}
+
+/// The theme computed for a widget that style rules apply to.
+pub(crate) struct StyledTheme(pub(crate) Theme);
+
+impl Component for StyledTheme {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Wakes the event loop whenever the file at `path` is modified, which reloads the style
+/// sheet loaded from it. The polling thread stops once the watcher is dropped.
+pub(crate) struct StyleWatcher {
+    stopped: Arc<AtomicBool>,
+    // Set before waking the loop, which timers and animations wake too.
+    changed: Arc<AtomicBool>,
+}
+
+impl StyleWatcher {
+    pub(crate) fn new(path: PathBuf, events_proxy: glutin::EventsLoopProxy) -> StyleWatcher {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let changed = Arc::new(AtomicBool::new(false));
+        let thread_stopped = Arc::clone(&stopped);
+        let thread_changed = Arc::clone(&changed);
+        thread::spawn(move || {
+            let mut last_modified = modified(&path);
+            loop {
+                thread::sleep(WATCH_INTERVAL);
+                if thread_stopped.load(Ordering::Relaxed) {
+                    break;
+                }
+                let current = modified(&path);
+                if current != last_modified {
+                    last_modified = current;
+                    thread_changed.store(true, Ordering::Relaxed);
+                    if events_proxy.wakeup().is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        StyleWatcher { stopped, changed }
+    }
+
+    /// Whether the file changed since the last call.
+    pub(crate) fn take_changed(&self) -> bool {
+        self.changed.swap(false, Ordering::Relaxed)
+    }
+}
+
+impl Drop for StyleWatcher {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// The last segment of a widget's type name, e.g. `Button` for
+/// `imagine_toolkit::button::Button`.
+pub(crate) fn short_type_name(type_name: &str) -> &str {
+    let type_name = type_name.split('<').next().unwrap_or(type_name);
+    type_name.rsplit("::").next().unwrap_or(type_name)
+}
+
+/// The offset of the first character of `part`, which starts at `offset`, that isn't
+/// whitespace, so errors report the line it is on.
+fn trimmed_start(part: &str, offset: usize) -> usize {
+    offset + part.len() - part.trim_start().len()
+}
+
+fn error<T>(text: &str, offset: usize, message: String) -> Result<T, StyleError> {
+    Err(StyleError::Parse {
+        line: text[..offset].matches('\n').count() + 1,
+        message,
+    })
+}
+
+fn parse_rules(text: &str) -> Result<Vec<Rule>, StyleError> {
+    let text = strip_comments(text);
+    let mut rules = Vec::new();
+    let mut position = 0;
+    loop {
+        let open = match text[position..].find('{') {
+            Some(open) => position + open,
+            None if text[position..].trim().is_empty() => break,
+            None => {
+                let start = trimmed_start(&text[position..], position);
+                return error(&text, start, "expected `{` after selectors".to_string());
+            }
+        };
+        let close = match text[open..].find('}') {
+            Some(close) => open + close,
+            None => return error(&text, open, "unclosed `{`".to_string()),
+        };
+
+        rules.push(Rule {
+            selectors: parse_selectors(&text, position..open)?,
+            declarations: parse_declarations(&text, open + 1..close)?,
+        });
+        position = close + 1;
+    }
+    Ok(rules)
+}
+
+/// Replaces comments with spaces, keeping newlines so errors report the right line.
+fn strip_comments(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find("*/")
+            .map_or(rest.len(), |end| start + end + 2);
+        for character in rest[start..end].chars() {
+            stripped.push(if character == '\n' { '\n' } else { ' ' });
+        }
+        rest = &rest[end..];
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+fn parse_selectors(
+    text: &str,
+    range: std::ops::Range<usize>,
+) -> Result<Vec<Selector>, StyleError> {
+    let mut selectors = Vec::new();
+    let mut offset = range.start;
+    for selector in text[range].split(',') {
+        let start = trimmed_start(selector, offset);
+        offset += selector.len() + 1;
+        let selector = selector.trim();
+        if selector == "*" {
+            selectors.push(Selector {
+                widget_type: None,
+                classes: Vec::new(),
+            });
+            continue;
+        }
+
+        let mut parts = selector.split('.');
+        let widget_type = parts.next().unwrap_or("");
+        let classes = parts.map(str::to_string).collect::<Vec<_>>();
+        // Only a type and classes are supported, not descendants, ids or pseudo-classes.
+        let valid = (widget_type.is_empty() || is_identifier(widget_type))
+            && (!widget_type.is_empty() || !classes.is_empty())
+            && classes.iter().all(|class| is_identifier(class));
+        if !valid {
+            return error(text, start, format!("invalid selector `{}`", selector));
+        }
+
+        selectors.push(Selector {
+            widget_type: if widget_type.is_empty() {
+                None
+            } else {
+                Some(widget_type.to_string())
+            },
+            classes,
+        });
+    }
+    Ok(selectors)
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|character| character.is_alphanumeric() || character == '-' || character == '_')
+}
+
+fn parse_declarations(
+    text: &str,
+    range: std::ops::Range<usize>,
+) -> Result<Vec<Declaration>, StyleError> {
+    let mut declarations = Vec::new();
+    let mut offset = range.start;
+    for declaration in text[range].split(';') {
+        let start = trimmed_start(declaration, offset);
+        offset += declaration.len() + 1;
+        if declaration.trim().is_empty() {
+            continue;
+        }
+
+        let (property, value) = match declaration.find(':') {
+            Some(colon) => (declaration[..colon].trim(), declaration[colon + 1..].trim()),
+            None => return error(text, start, format!("expected `:` in `{}`", declaration.trim())),
+        };
+        match parse_declaration(property, value) {
+            Ok(declaration) => declarations.push(declaration),
+            Err(message) => return error(text, start, message),
+        }
+    }
+    Ok(declarations)
+}
+
+fn parse_declaration(property: &str, value: &str) -> Result<Declaration, String> {
+    Ok(match property {
+        "color" => Declaration::Color(parse_color(value)?),
+        "background" | "background-color" => Declaration::Background(parse_color(value)?),
+        "surface-color" => Declaration::SurfaceColor(parse_color(value)?),
+        "accent-color" => Declaration::AccentColor(parse_color(value)?),
+        "border-color" => Declaration::BorderColor(parse_color(value)?),
+        "font-family" => {
+            let families = value
+                .split(',')
+                .map(|family| family.trim().trim_matches(&['"', '\''][..]).to_string())
+                .filter(|family| !family.is_empty())
+                .collect::<Vec<_>>();
+            if families.is_empty() {
+                return Err("expected a font family".to_string());
+            }
+            Declaration::FontFamily(families)
+        }
+        "font-size" => Declaration::FontSize(parse_length(value)?),
+        "font-weight" => Declaration::FontWeight(match value {
+            "normal" => FontWeight::NORMAL,
+            "bold" => FontWeight::BOLD,
+            _ => value
+                .parse()
+                .map(FontWeight)
+                .map_err(|_| format!("invalid font weight `{}`", value))?,
+        }),
+        "font-style" => Declaration::FontStyle(match value {
+            "normal" => FontStyle::Normal,
+            "italic" => FontStyle::Italic,
+            _ => return Err(format!("invalid font style `{}`", value)),
+        }),
+        "padding" => Declaration::Padding(parse_length(value)?),
+        "padding-small" => Declaration::PaddingSmall(parse_length(value)?),
+        "border-radius" => Declaration::BorderRadius(parse_length(value)?),
+        _ => return Err(format!("unknown property `{}`", property)),
+    })
+}
+
+fn parse_length(value: &str) -> Result<f32, String> {
+    value
+        .trim_end_matches("px")
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid length `{}`", value))
+}
+
+/// Parses `#rgb`, `#rrggbb`, `#rrggbbaa`, `rgb(r, g, b)`, `rgba(r, g, b, a)` and a few
+/// color names.
+fn parse_color(value: &str) -> Result<ColorF, String> {
+    let invalid = || format!("invalid color `{}`", value);
+    match value {
+        "black" => return Ok(ColorF::BLACK),
+        "white" => return Ok(ColorF::WHITE),
+        "transparent" => return Ok(ColorF::TRANSPARENT),
+        _ => {}
+    }
+
+    if let Some(hex) = value.strip_prefix('#') {
+        let digit = |index: usize, length: usize| {
+            u8::from_str_radix(hex.get(index..index + length).ok_or_else(invalid)?, 16)
+                .map_err(|_| invalid())
+        };
+        return match hex.len() {
+            3 => Ok(ColorF::new(
+                f32::from(digit(0, 1)? * 17) / 255.0,
+                f32::from(digit(1, 1)? * 17) / 255.0,
+                f32::from(digit(2, 1)? * 17) / 255.0,
+                1.0,
+            )),
+            6 | 8 => Ok(ColorF::new(
+                f32::from(digit(0, 2)?) / 255.0,
+                f32::from(digit(2, 2)?) / 255.0,
+                f32::from(digit(4, 2)?) / 255.0,
+                if hex.len() == 8 {
+                    f32::from(digit(6, 2)?) / 255.0
+                } else {
+                    1.0
+                },
+            )),
+            _ => Err(invalid()),
+        };
+    }
+
+    let arguments = value
+        .strip_prefix("rgba(")
+        .or_else(|| value.strip_prefix("rgb("))
+        .and_then(|rest| rest.strip_suffix(')'))
+        .ok_or_else(invalid)?
+        .split(',')
+        .map(|argument| argument.trim().parse::<f32>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, _>>()?;
+    match arguments[..] {
+        [r, g, b] => Ok(ColorF::new(r / 255.0, g / 255.0, b / 255.0, 1.0)),
+        [r, g, b, a] => Ok(ColorF::new(r / 255.0, g / 255.0, b / 255.0, a)),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error_line(text: &str) -> usize {
+        match StyleSheet::parse(text) {
+            Err(StyleError::Parse { line, .. }) => line,
+            result => panic!("expected a parse error, got {:?}", result),
+        }
+    }
+
+    fn font_size(style_sheet: &StyleSheet, widget_type: &str, classes: &[&str]) -> Option<f32> {
+        let classes = classes
+            .iter()
+            .map(|class| class.to_string())
+            .collect::<Vec<_>>();
+        style_sheet
+            .style(&Theme::default(), widget_type, &classes)
+            .map(|theme| theme.typography.body.size)
+    }
+
+    #[test]
+    fn comments_are_ignored() {
+        let style_sheet = StyleSheet::parse(
+            "/* A comment\n   over two lines. */\nButton { color: #fff; /* white */ }\n/* end */",
+        )
+        .unwrap();
+        assert_eq!(style_sheet.rules.len(), 1);
+        assert_eq!(
+            style_sheet.rules[0].declarations,
+            vec![Declaration::Color(ColorF::WHITE)]
+        );
+    }
+
+    #[test]
+    fn rules_have_several_selectors() {
+        let style_sheet =
+            StyleSheet::parse("Button, TextBox.search.large, * { padding: 4px; }").unwrap();
+        assert_eq!(
+            style_sheet.rules[0].selectors,
+            vec![
+                Selector {
+                    widget_type: Some("Button".to_string()),
+                    classes: vec![],
+                },
+                Selector {
+                    widget_type: Some("TextBox".to_string()),
+                    classes: vec!["search".to_string(), "large".to_string()],
+                },
+                Selector {
+                    widget_type: None,
+                    classes: vec![],
+                },
+            ]
+        );
+        assert_eq!(
+            style_sheet.rules[0].declarations,
+            vec![Declaration::Padding(4.0)]
+        );
+    }
+
+    #[test]
+    fn more_specific_selectors_win() {
+        let style_sheet = StyleSheet::parse(
+            ".primary { font-size: 10px; }
+            Button.primary { font-size: 20px; }
+            Button { font-size: 30px; }",
+        )
+        .unwrap();
+        assert_eq!(font_size(&style_sheet, "Button", &["primary"]), Some(20.0));
+        assert_eq!(
+            font_size(&style_sheet, "Button", &["primary", "wide"]),
+            Some(20.0)
+        );
+        assert_eq!(font_size(&style_sheet, "Label", &["primary"]), Some(10.0));
+        assert_eq!(font_size(&style_sheet, "Button", &[]), Some(30.0));
+        assert_eq!(font_size(&style_sheet, "Label", &["wide"]), None);
+    }
+
+    #[test]
+    fn later_rules_win_ties() {
+        let style_sheet = StyleSheet::parse(
+            "Button { font-size: 10px; }
+            Button { font-size: 20px; }",
+        )
+        .unwrap();
+        assert_eq!(font_size(&style_sheet, "Button", &[]), Some(20.0));
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(parse_color("black"), Ok(ColorF::BLACK));
+        assert_eq!(parse_color("#f00"), Ok(ColorF::new(1.0, 0.0, 0.0, 1.0)));
+        assert_eq!(parse_color("#00ff00"), Ok(ColorF::new(0.0, 1.0, 0.0, 1.0)));
+        assert_eq!(
+            parse_color("#0000ff00"),
+            Ok(ColorF::new(0.0, 0.0, 1.0, 0.0))
+        );
+        assert_eq!(
+            parse_color("rgb(255, 0, 255)"),
+            Ok(ColorF::new(1.0, 0.0, 1.0, 1.0))
+        );
+        assert_eq!(
+            parse_color("rgba(0, 0, 0, 0.5)"),
+            Ok(ColorF::new(0.0, 0.0, 0.0, 0.5))
+        );
+    }
+
+    #[test]
+    fn bad_colors_are_errors() {
+        for color in &[
+            "",
+            "blue",
+            "#",
+            "#12",
+            "#12345",
+            "#ggg",
+            "#ffé",
+            "rgb(1, 2)",
+            "rgb(1, 2, x)",
+            "rgba(1, 2, 3, 4, 5)",
+            "rgb(1, 2, 3",
+        ] {
+            assert_eq!(
+                parse_color(color),
+                Err(format!("invalid color `{}`", color)),
+            );
+        }
+    }
+
+    #[test]
+    fn errors_report_the_line_they_are_on() {
+        assert_eq!(parse_error_line("Button {\n    color: nope;\n}"), 2);
+        assert_eq!(
+            parse_error_line("Button { color: white; }\n\nButton > Label { color: white; }"),
+            3
+        );
+        assert_eq!(
+            parse_error_line("/* one\ntwo */\nButton {\n    colour: white;\n}"),
+            4
+        );
+        assert_eq!(parse_error_line("Button {\n    color white;\n}"), 2);
+        assert_eq!(parse_error_line("Button { color: white; }\nLabel"), 2);
+        assert_eq!(parse_error_line("\nButton {\n    color: white;\n"), 2);
+    }
+}
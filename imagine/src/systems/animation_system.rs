@@ -0,0 +1,65 @@
+use crate::{
+    animation::Clock, layout::NeedsLayout, render::NeedsPaint, widget::WidgetComponent,
+    Invalidation, WidgetId, WindowComponent,
+};
+use specs::{Join, Read, System, WriteStorage};
+
+/// Advances the animations of every widget to the current time, marking the windows whose
+/// widgets changed dirty and the widgets themselves as needing layout or paint.
+pub(crate) struct AnimationSystem;
+
+impl<'a> System<'a> for AnimationSystem {
+    type SystemData = (
+        WriteStorage<'a, WidgetComponent>,
+        WriteStorage<'a, NeedsLayout>,
+        WriteStorage<'a, NeedsPaint>,
+        WriteStorage<'a, WindowComponent>,
+        Read<'a, Clock>,
+    );
+
+    fn run(
+        &mut self,
+        (mut widgets, mut needs_layout, mut needs_paint, mut windows, clock): Self::SystemData,
+    ) {
+        for window in (&mut windows).join() {
+            fn visit(
+                widget_id: WidgetId,
+                widgets: &mut WriteStorage<WidgetComponent>,
+                needs_layout: &mut WriteStorage<NeedsLayout>,
+                needs_paint: &mut WriteStorage<NeedsPaint>,
+                clock: &Clock,
+            ) -> bool {
+                let widget = match widgets.get_mut(widget_id.0) {
+                    Some(widget) => widget,
+                    None => return false,
+                };
+                let invalidation = widget.animate(clock.now);
+                match invalidation {
+                    Invalidation::Layout => {
+                        needs_layout.insert(widget_id.0, NeedsLayout).ok();
+                    }
+                    Invalidation::Paint => {
+                        needs_paint.insert(widget_id.0, NeedsPaint).ok();
+                    }
+                    Invalidation::None => {}
+                }
+                let mut animating = invalidation != Invalidation::None;
+                for child in widget.children() {
+                    animating |= visit(child, widgets, needs_layout, needs_paint, clock);
+                }
+                animating
+            }
+
+            window.animating = visit(
+                window.root,
+                &mut widgets,
+                &mut needs_layout,
+                &mut needs_paint,
+                &clock,
+            );
+            if window.animating {
+                window.set_dirty(true);
+            }
+        }
+    }
+}
@@ -1,6 +1,11 @@
 use crate::{
+    clipboard::ClipboardResource,
     interactive::{Event, Interaction},
-    ClickListener, Message, MessageQueue, WidgetComponent,
+    layout::NeedsLayout,
+    render::NeedsPaint,
+    window::widget_windows,
+    ClickListener, EventListener, Invalidation, Message, MessageQueue, WidgetComponent,
+    WindowComponent,
 };
 use specs::{Entities, Join, System, Write, WriteStorage, ReadStorage};
 use std::marker::PhantomData;
@@ -22,26 +27,109 @@ impl<'a, M: Message> System<'a> for InteractionSystem<M> {
         Entities<'a>,
         WriteStorage<'a, Event>,
         WriteStorage<'a, WidgetComponent>,
+        WriteStorage<'a, NeedsLayout>,
+        WriteStorage<'a, NeedsPaint>,
         ReadStorage<'a, ClickListener<M>>,
+        ReadStorage<'a, EventListener<M>>,
         Write<'a, MessageQueue<M>>,
+        Write<'a, ClipboardResource>,
+        ReadStorage<'a, WindowComponent>,
     );
 
     fn run(
         &mut self,
-        (entities, mut events, mut widgets, listeners, mut queue): Self::SystemData,
+        (
+            entities,
+            mut events,
+            mut widgets,
+            mut needs_layout,
+            mut needs_paint,
+            listeners,
+            event_listeners,
+            mut queue,
+            mut clipboard,
+            windows,
+        ): Self::SystemData,
     ) {
-        for (event, widget) in (&mut events, &mut widgets).join() {
-            widget.handle_interaction(event.event);
+        for (entity, event, widget) in (&entities, &events, &mut widgets).join() {
+            let mut invalidation = Invalidation::None;
+            for interaction in &event.events {
+                widget.handle_interaction(*interaction);
+
+                match interaction {
+                    Interaction::Copy => {
+                        if let Some(text) = widget.copy() {
+                            clipboard.0.set_contents(text);
+                        }
+                    }
+                    Interaction::Cut => {
+                        if let Some(text) = widget.cut() {
+                            clipboard.0.set_contents(text);
+                        }
+                    }
+                    Interaction::Paste => {
+                        if let Some(text) = clipboard.0.get_contents() {
+                            widget.paste(&text);
+                        }
+                    }
+                    _ => {}
+                }
+
+                invalidation = invalidation.max(widget.interaction_invalidation(*interaction));
+            }
+            match invalidation {
+                Invalidation::Layout => {
+                    needs_layout.insert(entity, NeedsLayout).ok();
+                }
+                Invalidation::Paint => {
+                    needs_paint.insert(entity, NeedsPaint).ok();
+                }
+                Invalidation::None => {}
+            }
         }
 
-        for (entity, event) in (&entities, &mut events).join() {
-            if let Interaction::MouseDown = event.event {
-                if let Some(listener) = listeners.get(entity) {
-                    queue.0.push((listener.on_click)());
+        // The widgets that sent each message, to tag it with the widget's window.
+        let mut messages = Vec::new();
+
+        for (entity, event, widget) in (&entities, &events, &widgets).join() {
+            for interaction in &event.events {
+                if activates(widget, *interaction) {
+                    if let Some(listener) = listeners.get(entity) {
+                        messages.push((entity, (listener.on_click)()));
+                    }
                 }
             }
         }
 
+        for (entity, widget) in (&entities, &mut widgets).join() {
+            for event in widget.take_events() {
+                if let Some(listener) = event_listeners.get(entity) {
+                    if let Some(message) = (listener.on_event)(&*event) {
+                        messages.push((entity, message));
+                    }
+                }
+            }
+        }
+
+        if !messages.is_empty() {
+            let owners = widget_windows(&windows, &widgets);
+            for (entity, message) in messages {
+                queue.0.push((owners.get(&entity).copied(), message));
+            }
+        }
+
         events.clear();
     }
 }
+
+/// Clicking a widget, or pressing Enter or Space while an activatable widget is focused,
+/// activates its click listener. Keys held down activate it once.
+fn activates(widget: &WidgetComponent, interaction: Interaction) -> bool {
+    match interaction {
+        Interaction::Click => true,
+        Interaction::KeyDown(key_event) => {
+            key_event.is_activation() && !key_event.repeat && widget.activatable()
+        }
+        _ => false,
+    }
+}
@@ -1,24 +1,78 @@
 use crate::{
-    layout::{BoxConstraint, LayoutContext, Position, Size},
+    font::FontRegistry,
+    layout::{
+        BoxConstraint, LayoutCache, LayoutContext, LayoutStats, LayoutStorage, NeedsLayout,
+        Position, Size,
+    },
+    render::NeedsPaint,
+    style::StyledTheme,
     text::FinalText,
     widget::WidgetComponent,
-    WindowComponent,
+    Theme, WindowComponent,
 };
-use specs::{Join, ReadStorage, System, WriteStorage};
+use specs::{Entities, Join, Read, ReadStorage, System, Write, WriteStorage};
 
+/// Lays out the widgets of every dirty window, reusing the sizes of the widgets whose layout
+/// was not invalidated.
 pub(crate) struct LayoutSystem;
 
 impl<'a> System<'a> for LayoutSystem {
     type SystemData = (
+        Entities<'a>,
         WriteStorage<'a, Size>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, FinalText>,
+        WriteStorage<'a, LayoutCache>,
+        WriteStorage<'a, NeedsLayout>,
+        WriteStorage<'a, NeedsPaint>,
         ReadStorage<'a, WidgetComponent>,
-        ReadStorage<'a, WindowComponent>,
+        ReadStorage<'a, StyledTheme>,
+        WriteStorage<'a, WindowComponent>,
+        Read<'a, FontRegistry>,
+        Read<'a, Theme>,
+        Write<'a, LayoutStats>,
     );
 
-    fn run(&mut self, (mut sizes, mut positions, mut text, widgets, windows): Self::SystemData) {
-        for window in windows.join() {
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut sizes,
+            mut positions,
+            mut text,
+            mut caches,
+            mut needs_layout,
+            mut needs_paint,
+            widgets,
+            styled,
+            mut windows,
+            fonts,
+            theme,
+            mut stats,
+        ): Self::SystemData,
+    ) {
+        let invalidated = (&entities, &needs_layout)
+            .join()
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+
+        let mut layout_context = LayoutContext::new(
+            LayoutStorage {
+                positions: &mut positions,
+                sizes: &mut sizes,
+                text: &mut text,
+                caches: &mut caches,
+                needs_layout: &mut needs_layout,
+                needs_paint: &mut needs_paint,
+                widgets: &widgets,
+                styled: &styled,
+            },
+            &fonts,
+            &theme,
+            &mut stats,
+        );
+
+        for window in (&mut windows).join() {
             if !window.dirty() {
                 continue;
             }
@@ -28,15 +82,10 @@ impl<'a> System<'a> for LayoutSystem {
                 Size::new(layout_size.width, layout_size.height),
             );
 
-            let mut layout_context = LayoutContext::new(
-                &mut positions,
-                &mut sizes,
-                &mut text,
-                &widgets,
-                &window.font,
-            );
-            layout_context.layout_widget(window.root, constraint);
-            positions.insert(window.root.0, Position::zero()).ok();
+            // A resize changes the root's constraint, which lays out whatever depends on it.
+            layout_context.layout_root(window.root, constraint, window.relayout);
+            window.relayout = false;
+            layout_context.layout_invalidated(window.root, &invalidated);
         }
     }
 }
@@ -1,7 +1,11 @@
+mod animation_system;
 mod interaction_system;
 mod layout_system;
 mod render_system;
+mod style_system;
 
+pub(crate) use self::animation_system::AnimationSystem;
 pub(crate) use self::interaction_system::InteractionSystem;
 pub(crate) use self::layout_system::LayoutSystem;
 pub(crate) use self::render_system::RenderSystem;
+pub(crate) use self::style_system::StyleSystem;
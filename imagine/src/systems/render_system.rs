@@ -1,13 +1,33 @@
 use crate::{
-    text::FinalText, Geometry, Interactive, Position, RenderContext, Size, WidgetComponent,
-    WidgetId, WindowComponent,
+    font::FontRegistry,
+    render::{NeedsPaint, PaintCache, PaintStats},
+    style::StyledTheme,
+    text::FinalText,
+    Geometry, Interactive, Position, RenderContext, Size, Theme, WidgetComponent, WidgetId,
+    WindowComponent,
 };
-use specs::{Entities, Join, ReadStorage, System, WriteStorage};
+use specs::{Entities, Join, Read, ReadStorage, System, Write, WriteStorage};
 use webrender::api::*;
 use webrender::api::units::*;
 
+/// Builds the display list of every dirty window, replaying the display items widgets recorded
+/// in earlier frames unless their paint was invalidated.
 pub(crate) struct RenderSystem;
 
+/// The storages rendering a widget tree reads and writes.
+struct RenderStorage<'a, 'b> {
+    entities: &'a Entities<'b>,
+    positions: &'a ReadStorage<'b, Position>,
+    sizes: &'a ReadStorage<'b, Size>,
+    widgets: &'a ReadStorage<'b, WidgetComponent>,
+    text: &'a ReadStorage<'b, FinalText>,
+    styled: &'a ReadStorage<'b, StyledTheme>,
+    interactive: &'a mut WriteStorage<'b, Interactive>,
+    paint_caches: &'a mut WriteStorage<'b, PaintCache>,
+    needs_paint: &'a mut WriteStorage<'b, NeedsPaint>,
+    stats: &'a mut PaintStats,
+}
+
 impl<'a> System<'a> for RenderSystem {
     type SystemData = (
         Entities<'a>,
@@ -15,14 +35,47 @@ impl<'a> System<'a> for RenderSystem {
         ReadStorage<'a, Position>,
         ReadStorage<'a, WidgetComponent>,
         ReadStorage<'a, FinalText>,
+        ReadStorage<'a, StyledTheme>,
         WriteStorage<'a, WindowComponent>,
         WriteStorage<'a, Interactive>,
+        WriteStorage<'a, PaintCache>,
+        WriteStorage<'a, NeedsPaint>,
+        Read<'a, FontRegistry>,
+        Read<'a, Theme>,
+        Write<'a, PaintStats>,
     );
 
     fn run(
         &mut self,
-        (entities, sizes, positions, widgets, text, mut windows, mut interactive): Self::SystemData,
+        (
+            entities,
+            sizes,
+            positions,
+            widgets,
+            text,
+            styled,
+            mut windows,
+            mut interactive,
+            mut paint_caches,
+            mut needs_paint,
+            font_registry,
+            theme,
+            mut stats,
+        ): Self::SystemData,
     ) {
+        let mut storage = RenderStorage {
+            entities: &entities,
+            positions: &positions,
+            sizes: &sizes,
+            widgets: &widgets,
+            text: &text,
+            styled: &styled,
+            interactive: &mut interactive,
+            paint_caches: &mut paint_caches,
+            needs_paint: &mut needs_paint,
+            stats: &mut stats,
+        };
+
         for window in (&mut windows).join() {
             if !window.dirty() {
                 continue;
@@ -45,65 +98,110 @@ impl<'a> System<'a> for RenderSystem {
                 false,
             );
 
-            let mut render_context = RenderContext::new(&mut builder, window.font_instance_key, window.pipeline_id);
-
-            fn render_entities(
-                children: &[WidgetId],
-                data: &(
-                    &ReadStorage<Position>,
-                    &ReadStorage<Size>,
-                    &ReadStorage<WidgetComponent>,
+            // The window's background is drawn rather than left to the renderer's clear color
+            // so that switching themes takes effect.
+            let layout_size = window.layout_size();
+            builder.push_rect(
+                &CommonItemProperties::new(
+                    LayoutRect::new(LayoutPoint::zero(), layout_size),
+                    SpaceAndClipInfo::root_scroll(window.pipeline_id),
                 ),
-                texts: &ReadStorage<FinalText>,
-                interactive: &mut WriteStorage<Interactive>,
-                render_context: &mut RenderContext,
-                entities: &Entities,
-                offset: Position,
-            ) {
-                for widget_id in children {
-                    let (position, size, widget) = data.join().get(widget_id.0, entities).unwrap();
-                    let new_position = Position::new(offset.x + position.x, offset.y + position.y);
-                    let text = texts.get(widget_id.0);
-                    let box_size = Geometry::new(new_position, *size);
-
-                    match widget.render(*widget_id, box_size, text, render_context) {
-                        Some(tag) => {
-                            if let Some(interactive) = interactive.get_mut(widget_id.0) {
-                                interactive.tag = tag;
-                            } else {
-                                interactive.insert(widget_id.0, Interactive::new(tag)).ok();
-                            }
-                        }
-                        None => {
-                            interactive.remove(widget_id.0);
-                        }
-                    }
-
-                    render_entities(
-                        &widget.children(),
-                        data,
-                        texts,
-                        interactive,
-                        render_context,
-                        entities,
-                        new_position,
-                    );
-                }
-            }
+                theme.palette.background,
+            );
 
-            render_entities(
-                &[window.root],
-                &(&positions, &sizes, &widgets),
-                &text,
-                &mut interactive,
-                &mut render_context,
-                &entities,
-                Position::zero(),
+            let mut render_context = RenderContext::new(
+                &mut builder,
+                &mut window.fonts,
+                &font_registry,
+                &theme,
+                window.pipeline_id,
             );
 
+            render_entities(&[window.root], &mut storage, &mut render_context, Position::zero());
+
             builder.pop_stacking_context();
 
             window.display_list_builder = Some(builder);
         }
     }
 }
+
+fn render_entities<'a: 'r, 'r>(
+    children: &[WidgetId],
+    storage: &mut RenderStorage<'a, '_>,
+    render_context: &mut RenderContext<'r>,
+    offset: Position,
+) {
+    let (entities, positions, sizes, widgets) =
+        (storage.entities, storage.positions, storage.sizes, storage.widgets);
+    let styled = storage.styled;
+    for widget_id in children {
+        let (position, size, widget) = (positions, sizes, widgets)
+            .join()
+            .get(widget_id.0, entities)
+            .unwrap();
+        let new_position = Position::new(offset.x + position.x, offset.y + position.y);
+        let text = storage.text.get(widget_id.0);
+        let box_size = Geometry::new(new_position, *size);
+        // Widgets may replace the space and clip for their children, e.g. to clip them, so it
+        // is restored once the subtree has been rendered.
+        let space_and_clip = render_context.current_space_and_clip;
+        let theme = styled
+            .get(widget_id.0)
+            .map(|styled| &styled.0)
+            .or_else(|| widget.theme());
+        if let Some(theme) = theme {
+            render_context.push_theme(theme);
+        }
+
+        let first_tag = render_context.peek_tag_identifier();
+        let needs_paint = storage.needs_paint.contains(widget_id.0);
+        let cache = storage
+            .paint_caches
+            .get(widget_id.0)
+            .filter(|cache| !needs_paint && cache.is_valid(box_size, space_and_clip, first_tag));
+        match cache {
+            Some(cache) => {
+                render_context.current_space_and_clip = cache.replay(&mut render_context.builder);
+                render_context.skip_tag_identifiers(cache.tags());
+                storage.stats.reused += 1;
+            }
+            None => {
+                match widget.render(*widget_id, box_size, text, render_context) {
+                    Some(tag) => {
+                        if let Some(interactive) = storage.interactive.get_mut(widget_id.0) {
+                            interactive.tag = tag;
+                        } else {
+                            storage
+                                .interactive
+                                .insert(widget_id.0, Interactive::new(tag))
+                                .ok();
+                        }
+                    }
+                    None => {
+                        storage.interactive.remove(widget_id.0);
+                    }
+                }
+
+                let cache = PaintCache::record(
+                    &mut render_context.builder,
+                    box_size,
+                    space_and_clip,
+                    render_context.current_space_and_clip,
+                    first_tag,
+                    render_context.peek_tag_identifier(),
+                );
+                storage.paint_caches.insert(widget_id.0, cache).ok();
+                storage.needs_paint.remove(widget_id.0);
+                storage.stats.painted += 1;
+            }
+        }
+
+        render_entities(&widget.children(), storage, render_context, new_position);
+
+        render_context.current_space_and_clip = space_and_clip;
+        if theme.is_some() {
+            render_context.pop_theme();
+        }
+    }
+}
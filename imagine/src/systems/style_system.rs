@@ -0,0 +1,170 @@
+use crate::{
+    layout::{LayoutCache, NeedsLayout},
+    style::{StyleSheet, StyledTheme},
+    widget::WidgetComponent,
+    Theme, WidgetId, WindowComponent,
+};
+use specs::{Entities, Join, Read, ReadStorage, System, WriteStorage};
+
+/// Computes the theme of every widget that style sheet rules apply to before it is laid out.
+///
+/// Every widget of a window is styled again when the style sheet or the theme changed, and
+/// otherwise only the widgets whose layout was invalidated, e.g. by adding a class, along with
+/// their descendants. Widgets whose theme changed are laid out again along with their
+/// descendants, which inherit it.
+pub(crate) struct StyleSystem;
+
+impl<'a> System<'a> for StyleSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, StyledTheme>,
+        WriteStorage<'a, NeedsLayout>,
+        ReadStorage<'a, LayoutCache>,
+        ReadStorage<'a, WidgetComponent>,
+        ReadStorage<'a, WindowComponent>,
+        Read<'a, StyleSheet>,
+        Read<'a, Theme>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut styled,
+            mut needs_layout,
+            caches,
+            widgets,
+            windows,
+            style_sheet,
+            theme,
+        ): Self::SystemData,
+    ) {
+        let invalidated = (&entities, &needs_layout)
+            .join()
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+
+        for window in windows.join() {
+            if !window.dirty() {
+                continue;
+            }
+            if style_sheet.is_empty() {
+                styled.clear();
+                continue;
+            }
+
+            if window.relayout {
+                visit(
+                    window.root,
+                    &theme,
+                    false,
+                    &widgets,
+                    &mut styled,
+                    &mut needs_layout,
+                    &style_sheet,
+                );
+                continue;
+            }
+
+            for &entity in &invalidated {
+                // The widget followed by its ancestors, as of their last layout.
+                let mut path = vec![entity];
+                while let Some(parent) = caches
+                    .get(*path.last().unwrap())
+                    .and_then(LayoutCache::parent)
+                {
+                    // The parents of detached widgets are stale and may lead back to them.
+                    if path.contains(&parent) {
+                        break;
+                    }
+                    path.push(parent);
+                }
+                // Widgets that were never laid out are styled along with their parent, whose
+                // layout is invalidated when its children change.
+                if *path.last().unwrap() != window.root.0 {
+                    continue;
+                }
+                if path[1..]
+                    .iter()
+                    .any(|&ancestor| needs_layout.contains(ancestor))
+                {
+                    continue;
+                }
+
+                // The theme of the nearest ancestor that has one of its own.
+                let inherited = path[1..]
+                    .iter()
+                    .find_map(|&ancestor| {
+                        styled
+                            .get(ancestor)
+                            .map(|styled| &styled.0)
+                            .or_else(|| widgets.get(ancestor).and_then(|widget| widget.theme()))
+                    })
+                    .unwrap_or(&*theme)
+                    .clone();
+                visit(
+                    WidgetId(entity),
+                    &inherited,
+                    false,
+                    &widgets,
+                    &mut styled,
+                    &mut needs_layout,
+                    &style_sheet,
+                );
+            }
+        }
+    }
+}
+
+fn visit(
+    widget_id: WidgetId,
+    theme: &Theme,
+    changed: bool,
+    widgets: &ReadStorage<WidgetComponent>,
+    styled: &mut WriteStorage<StyledTheme>,
+    needs_layout: &mut WriteStorage<NeedsLayout>,
+    style_sheet: &StyleSheet,
+) {
+    let widget = match widgets.get(widget_id.0) {
+        Some(widget) => widget,
+        None => return,
+    };
+    // A widget's own theme replaces the inherited one before rules apply to it.
+    let theme = widget.theme().unwrap_or(theme);
+    let widget_theme = style_sheet.style(theme, widget.type_name(), &widget.classes);
+    let previous = styled.get(widget_id.0).map(|styled| &styled.0);
+    let changed = changed || previous != widget_theme.as_ref();
+    if changed {
+        needs_layout.insert(widget_id.0, NeedsLayout).ok();
+    }
+    match widget_theme {
+        Some(widget_theme) => {
+            for child in widget.children() {
+                visit(
+                    child,
+                    &widget_theme,
+                    changed,
+                    widgets,
+                    styled,
+                    needs_layout,
+                    style_sheet,
+                );
+            }
+            styled.insert(widget_id.0, StyledTheme(widget_theme)).ok();
+        }
+        None => {
+            for child in widget.children() {
+                visit(
+                    child,
+                    theme,
+                    changed,
+                    widgets,
+                    styled,
+                    needs_layout,
+                    style_sheet,
+                );
+            }
+            styled.remove(widget_id.0);
+        }
+    }
+}
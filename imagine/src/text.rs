@@ -1,67 +1,1081 @@
-use rusttype::{point, Font, Scale};
+use crate::{
+    font::{scale, FontInstance, FontRegistry, FontStyle, FontWeight, TextStyle},
+    shaping::{reorder, shape_paragraph, ShapedGlyph, SpanFonts},
+    Position, RenderContext,
+};
+use rusttype::GlyphId;
 use webrender::api::*;
 use webrender::api::units::*;
 use specs::{Component, DenseVecStorage};
+use std::ops::Range;
 
-#[derive(Debug)]
+const ELLIPSIS: char = '\u{2026}';
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+    /// Stretches the spaces of every wrapped line so it fills the width. The last line of a
+    /// paragraph is left aligned.
+    Justify,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TextOverflow {
+    Clip,
+    /// Ends the last visible line with "…" when text is cut off.
+    Ellipsis,
+}
+
+/// How text is broken into lines and placed within them.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ParagraphStyle {
+    pub align: TextAlign,
+    /// Whether lines wider than the available width wrap at word boundaries.
+    pub wrap: bool,
+    pub max_lines: Option<usize>,
+    pub overflow: TextOverflow,
+}
+
+impl Default for ParagraphStyle {
+    fn default() -> ParagraphStyle {
+        ParagraphStyle {
+            align: TextAlign::Left,
+            wrap: false,
+            max_lines: None,
+            overflow: TextOverflow::Clip,
+        }
+    }
+}
+
+/// A run of text within `RichText` drawn with its own attributes.
+///
+/// Attributes left unset are taken from the `TextStyle` the text is laid out with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextSpan {
+    pub text: String,
+    /// The text color, the color of the `TextPaint` the text is rendered with if unset.
+    pub color: Option<ColorF>,
+    pub size: Option<f32>,
+    pub weight: Option<FontWeight>,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub background: Option<ColorF>,
+}
+
+impl TextSpan {
+    pub fn new<T: Into<String>>(text: T) -> TextSpan {
+        TextSpan {
+            text: text.into(),
+            color: None,
+            size: None,
+            weight: None,
+            italic: false,
+            underline: false,
+            strikethrough: false,
+            background: None,
+        }
+    }
+
+    pub fn with_color(self, color: ColorF) -> TextSpan {
+        TextSpan {
+            color: Some(color),
+            ..self
+        }
+    }
+
+    pub fn with_size(self, size: f32) -> TextSpan {
+        TextSpan {
+            size: Some(size),
+            ..self
+        }
+    }
+
+    pub fn with_weight(self, weight: FontWeight) -> TextSpan {
+        TextSpan {
+            weight: Some(weight),
+            ..self
+        }
+    }
+
+    pub fn italic(self) -> TextSpan {
+        TextSpan {
+            italic: true,
+            ..self
+        }
+    }
+
+    pub fn underline(self) -> TextSpan {
+        TextSpan {
+            underline: true,
+            ..self
+        }
+    }
+
+    pub fn strikethrough(self) -> TextSpan {
+        TextSpan {
+            strikethrough: true,
+            ..self
+        }
+    }
+
+    pub fn with_background(self, background: ColorF) -> TextSpan {
+        TextSpan {
+            background: Some(background),
+            ..self
+        }
+    }
+
+    fn text_style(&self, base: &TextStyle) -> TextStyle {
+        TextStyle {
+            size: self.size.unwrap_or(base.size),
+            weight: self.weight.unwrap_or(base.weight),
+            style: if self.italic {
+                FontStyle::Italic
+            } else {
+                base.style
+            },
+            ..base.clone()
+        }
+    }
+}
+
+/// Text made of spans with different attributes, laid out as one paragraph flow.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RichText {
+    pub spans: Vec<TextSpan>,
+}
+
+impl RichText {
+    pub fn new() -> RichText {
+        RichText::default()
+    }
+
+    pub fn push(mut self, span: TextSpan) -> RichText {
+        self.spans.push(span);
+        self
+    }
+
+    /// The text of all spans joined together.
+    pub fn text(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+}
+
+impl From<String> for RichText {
+    fn from(text: String) -> RichText {
+        RichText::new().push(TextSpan::new(text))
+    }
+}
+
+impl From<&str> for RichText {
+    fn from(text: &str) -> RichText {
+        RichText::from(text.to_string())
+    }
+}
+
+/// How `FinalText::render` draws text.
+///
+/// Colors and decorations set on rich text spans apply on top of these.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextPaint {
+    pub color: ColorF,
+    /// Multiplies the alpha of the text, its backgrounds, decorations and shadow.
+    pub opacity: f32,
+    /// How glyphs are antialiased. Webrender picks the best supported mode if unset.
+    pub render_mode: Option<FontRenderMode>,
+    pub shadow: Option<Shadow>,
+    pub underline: bool,
+    pub strikethrough: bool,
+}
+
+impl TextPaint {
+    pub fn with_color(self, color: ColorF) -> TextPaint {
+        TextPaint { color, ..self }
+    }
+
+    pub fn with_opacity(self, opacity: f32) -> TextPaint {
+        TextPaint { opacity, ..self }
+    }
+
+    pub fn with_render_mode(self, render_mode: FontRenderMode) -> TextPaint {
+        TextPaint {
+            render_mode: Some(render_mode),
+            ..self
+        }
+    }
+
+    pub fn with_shadow(self, shadow: Shadow) -> TextPaint {
+        TextPaint {
+            shadow: Some(shadow),
+            ..self
+        }
+    }
+
+    pub fn underline(self) -> TextPaint {
+        TextPaint {
+            underline: true,
+            ..self
+        }
+    }
+
+    pub fn strikethrough(self) -> TextPaint {
+        TextPaint {
+            strikethrough: true,
+            ..self
+        }
+    }
+
+    fn apply_opacity(&self, color: ColorF) -> ColorF {
+        ColorF {
+            a: color.a * self.opacity,
+            ..color
+        }
+    }
+}
+
+impl Default for TextPaint {
+    fn default() -> TextPaint {
+        TextPaint {
+            color: ColorF::BLACK,
+            opacity: 1.0,
+            render_mode: None,
+            shadow: None,
+            underline: false,
+            strikethrough: false,
+        }
+    }
+}
+
+/// The placement of one line of laid out text.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LineMetrics {
+    /// The offset of the line's top from the top of the text.
+    pub top: f32,
+    /// The offset of the line's baseline from the top of the text.
+    pub baseline: f32,
+    pub height: f32,
+    pub width: f32,
+}
+
+/// The character found under a point by `FinalText::hit_test`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct TextHit {
+    /// The index of the span the character belongs to.
+    pub span: usize,
+    /// The index of the character within the whole text.
+    pub index: usize,
+}
+
+#[derive(Clone, Debug)]
 pub struct FinalText {
-    glyphs: Vec<GlyphInstance>,
+    glyphs: Vec<PlacedGlyph>,
+    spans: Vec<SpanPaint>,
+    lines: Vec<LineMetrics>,
     width: f32,
+    height: f32,
 }
 
 impl Component for FinalText {
     type Storage = DenseVecStorage<Self>;
 }
 
+#[derive(Copy, Clone, Debug)]
+struct PlacedGlyph {
+    instance: GlyphInstance,
+    // The pen position the glyph was placed at, before its offset.
+    x: f32,
+    advance: f32,
+    // The index of the character the glyph was produced for.
+    cluster: usize,
+    // The bidi embedding level, odd for right-to-left text.
+    level: u8,
+    // Differs from the span's font for fallback glyphs.
+    font: FontInstance,
+    span: usize,
+    line: usize,
+}
+
+/// The attributes of a span that only matter when drawing it.
+#[derive(Copy, Clone, Debug)]
+struct SpanPaint {
+    color: Option<ColorF>,
+    background: Option<ColorF>,
+    underline: bool,
+    strikethrough: bool,
+    size: f32,
+}
+
+/// The vertical metrics of a span's primary font at the span's size.
+struct SpanMetrics {
+    ascent: f32,
+    descent: f32,
+    line_gap: f32,
+}
+
+struct Line {
+    glyphs: Vec<ShapedGlyph>,
+    // The span whose metrics an empty line takes.
+    span: usize,
+    // Whether the line ends its paragraph, either at a newline or at the end of the text.
+    last_in_paragraph: bool,
+    truncated: bool,
+}
+
 impl FinalText {
-    pub(crate) fn new(font: &Font, text: &str) -> FinalText {
-        let height: f32 = 20.0;
+    /// Shapes and lays out `text`, wrapping it to `max_width` if `paragraph` allows it.
+    ///
+    /// Pass `f32::INFINITY` as `max_width` to only break lines at newlines.
+    pub(crate) fn new(
+        registry: &FontRegistry,
+        style: &TextStyle,
+        text: &str,
+        paragraph: &ParagraphStyle,
+        max_width: f32,
+    ) -> FinalText {
+        FinalText::rich(registry, style, &RichText::from(text), paragraph, max_width)
+    }
+
+    /// Lays out the spans of `text` as one flow, each with `style` changed by its attributes.
+    pub(crate) fn rich(
+        registry: &FontRegistry,
+        style: &TextStyle,
+        text: &RichText,
+        paragraph: &ParagraphStyle,
+        max_width: f32,
+    ) -> FinalText {
+        // Even empty text needs a span for the height of its line.
+        let empty = TextSpan::new("");
+        let mut spans = text.spans.iter().collect::<Vec<_>>();
+        if spans.is_empty() {
+            spans.push(&empty);
+        }
 
-        let scale = Scale {
-            x: height * 2.0,
-            y: height,
+        let mut span_fonts = Vec::with_capacity(spans.len());
+        let mut span_metrics = Vec::with_capacity(spans.len());
+        let mut offset = 0;
+        for span in &spans {
+            let span_style = span.text_style(style);
+            let chain = registry.fallback_chain(&span_style);
+            let size = registry.resolve(&span_style).size;
+            // Line metrics come from the span's own font, fallback fonts only provide glyphs.
+            let font = registry.font(chain[0]);
+            let v_metrics = font.v_metrics(scale(font, size));
+            span_metrics.push(SpanMetrics {
+                ascent: v_metrics.ascent,
+                descent: v_metrics.descent,
+                line_gap: v_metrics.line_gap,
+            });
+            span_fonts.push(SpanFonts {
+                range: offset..offset + span.text.len(),
+                chain,
+                size,
+            });
+            offset += span.text.len();
+        }
+        let span_at = |offset: usize| {
+            span_fonts
+                .iter()
+                .position(|span| span.range.contains(&offset))
+                .unwrap_or(span_fonts.len() - 1)
         };
-        let v_metrics = font.v_metrics(scale);
-        let offset = point(0.0, v_metrics.ascent + 10.0);
-
-        let (glyphs, width) = font.layout(text, scale, offset).fold(
-            (Vec::new(), 0.0f32),
-            |(mut glyphs, max_width), glyph| {
-                let position = glyph.position();
-                glyphs.push(GlyphInstance {
-                    index: glyph.id().0,
-                    point: LayoutPoint::new(position.x, position.y),
+
+        let full_text = text.text();
+        let mut lines = Vec::new();
+        let mut index = 0;
+        let mut offset = 0;
+        for paragraph_text in full_text.split('\n') {
+            let range = offset..offset + paragraph_text.len();
+            let span = span_at(offset);
+            let shaped = shape_paragraph(registry, &span_fonts, &full_text, range, index);
+            index += paragraph_text.chars().count() + 1;
+            offset += paragraph_text.len() + 1;
+
+            let first_line = lines.len();
+            if paragraph.wrap {
+                wrap(shaped.glyphs, span, max_width, &mut lines);
+            } else {
+                lines.push(Line {
+                    glyphs: shaped.glyphs,
+                    span,
+                    last_in_paragraph: true,
+                    truncated: false,
                 });
-                (
-                    glyphs,
-                    max_width.max(position.x + glyph.unpositioned().h_metrics().advance_width),
-                )
-            },
-        );
+            }
+
+            // Whitespace at the end of a line takes the paragraph's direction.
+            for line in &mut lines[first_line..] {
+                let end = trim_end(&line.glyphs).len();
+                for glyph in &mut line.glyphs[end..] {
+                    glyph.level = shaped.level;
+                }
+            }
+        }
+
+        if let Some(max_lines) = paragraph.max_lines {
+            if lines.len() > max_lines {
+                lines.truncate(max_lines.max(1));
+                if let Some(line) = lines.last_mut() {
+                    line.truncated = true;
+                }
+            }
+        }
 
-        FinalText { glyphs, width }
+        if paragraph.overflow == TextOverflow::Ellipsis {
+            for line in &mut lines {
+                if line.truncated || line_width(&line.glyphs) > max_width {
+                    ellipsize(registry, &span_fonts, line, max_width);
+                }
+            }
+        }
+
+        let widths = lines
+            .iter()
+            .map(|line| line_width(&line.glyphs))
+            .collect::<Vec<_>>();
+        let width = widths.iter().copied().fold(0.0f32, f32::max);
+        let align_width = if max_width.is_finite() {
+            max_width
+        } else {
+            width
+        };
+
+        let mut glyphs = Vec::new();
+        let mut metrics = Vec::new();
+        let mut top = 0.0;
+        for (line_index, (line, used_width)) in lines.iter_mut().zip(widths).enumerate() {
+            // The line is as tall as the tallest span on it.
+            let mut ascent = span_metrics[line.span].ascent;
+            let mut descent = span_metrics[line.span].descent;
+            let mut line_gap = span_metrics[line.span].line_gap;
+            for glyph in &line.glyphs {
+                let span = &span_metrics[glyph.span];
+                ascent = ascent.max(span.ascent);
+                descent = descent.min(span.descent);
+                line_gap = line_gap.max(span.line_gap);
+            }
+            let baseline = top + ascent;
+            let height = ascent - descent + line_gap;
+
+            let extra = (align_width - used_width).max(0.0);
+            let spaces = trim_end(&line.glyphs)
+                .iter()
+                .filter(|glyph| glyph.whitespace)
+                .count();
+
+            let (mut x, space_extra) = match paragraph.align {
+                TextAlign::Left => (0.0, 0.0),
+                TextAlign::Center => (extra / 2.0, 0.0),
+                TextAlign::Right => (extra, 0.0),
+                TextAlign::Justify if !line.last_in_paragraph && spaces > 0 => {
+                    (0.0, extra / spaces as f32)
+                }
+                TextAlign::Justify => (0.0, 0.0),
+            };
+
+            reorder(&mut line.glyphs);
+            for glyph in &line.glyphs {
+                let advance = if glyph.whitespace {
+                    glyph.advance + space_extra
+                } else {
+                    glyph.advance
+                };
+                glyphs.push(PlacedGlyph {
+                    instance: GlyphInstance {
+                        index: glyph.id,
+                        point: LayoutPoint::new(x + glyph.x_offset, baseline - glyph.y_offset),
+                    },
+                    x,
+                    advance,
+                    cluster: glyph.cluster,
+                    level: glyph.level,
+                    font: FontInstance {
+                        font: glyph.font,
+                        size: span_fonts[glyph.span].size,
+                    },
+                    span: glyph.span,
+                    line: line_index,
+                });
+                x += advance;
+            }
+
+            metrics.push(LineMetrics {
+                top,
+                baseline,
+                height,
+                width: used_width,
+            });
+            top += height;
+        }
+
+        FinalText {
+            glyphs,
+            spans: spans
+                .iter()
+                .zip(&span_fonts)
+                .map(|(span, fonts)| SpanPaint {
+                    color: span.color,
+                    background: span.background,
+                    underline: span.underline,
+                    strikethrough: span.strikethrough,
+                    size: fonts.size.to_f32_px(),
+                })
+                .collect(),
+            width,
+            height: top,
+            lines: metrics,
+        }
     }
 
+    /// Pushes the glyphs with their top left corner at `origin`, drawn as `paint` asks.
+    ///
+    /// Span backgrounds are drawn first, then one text item for each run of glyphs that share
+    /// a span and a font, followed by the run's underline and strikethrough. The shadow is
+    /// cast by the text and its decorations but not by the backgrounds.
     pub fn render(
         &self,
         info: &CommonItemProperties,
         bounds: LayoutRect,
         origin: LayoutPoint,
-        builder: &mut DisplayListBuilder,
-        font_instance_key: FontInstanceKey,
+        paint: &TextPaint,
+        render_context: &mut RenderContext,
     ) {
-        let glyphs = self
-            .glyphs
+        for run in self.runs() {
+            let first = &self.glyphs[run.start];
+            if let Some(background) = self.spans[first.span].background {
+                let line = &self.lines[first.line];
+                let (x0, x1) = self.extent(run.clone());
+                let rect = LayoutRect::new(
+                    LayoutPoint::new(origin.x + x0, origin.y + line.top),
+                    LayoutSize::new(x1 - x0, line.height),
+                );
+                render_context.builder.push_rect(
+                    &CommonItemProperties {
+                        clip_rect: rect,
+                        ..*info
+                    },
+                    paint.apply_opacity(background),
+                );
+            }
+        }
+
+        if let Some(shadow) = paint.shadow {
+            render_context.builder.push_shadow(
+                &SpaceAndClipInfo {
+                    spatial_id: info.spatial_id,
+                    clip_id: info.clip_id,
+                },
+                Shadow {
+                    color: paint.apply_opacity(shadow.color),
+                    ..shadow
+                },
+                true,
+            );
+        }
+
+        let glyph_options = paint.render_mode.map(|render_mode| GlyphOptions {
+            render_mode,
+            flags: FontInstanceFlags::empty(),
+        });
+        for run in self.runs() {
+            let first = self.glyphs[run.start];
+            let span = self.spans[first.span];
+            let color = paint.apply_opacity(span.color.unwrap_or(paint.color));
+            let glyphs = self.glyphs[run.clone()]
+                .iter()
+                .map(|glyph| GlyphInstance {
+                    index: glyph.instance.index,
+                    point: LayoutPoint::new(
+                        glyph.instance.point.x + origin.x,
+                        glyph.instance.point.y + origin.y,
+                    ),
+                })
+                .collect::<Vec<_>>();
+
+            let font_instance_key = render_context.font_instance_key(first.font);
+            render_context.builder.push_text(
+                info,
+                bounds,
+                &glyphs,
+                font_instance_key,
+                color,
+                glyph_options,
+            );
+
+            let baseline = origin.y + self.lines[first.line].baseline;
+            // Fonts' own decoration metrics aren't available, so they are derived from the size.
+            let thickness = (span.size / 16.0).max(1.0);
+            let mut decorations = Vec::new();
+            if span.underline || paint.underline {
+                decorations.push(baseline + span.size * 0.1);
+            }
+            if span.strikethrough || paint.strikethrough {
+                decorations.push(baseline - span.size * 0.3);
+            }
+            let (x0, x1) = self.extent(run);
+            for y in decorations {
+                let area = LayoutRect::new(
+                    LayoutPoint::new(origin.x + x0, y - thickness / 2.0),
+                    LayoutSize::new(x1 - x0, thickness),
+                );
+                render_context.builder.push_line(
+                    &CommonItemProperties {
+                        clip_rect: area,
+                        ..*info
+                    },
+                    &area,
+                    0.0,
+                    LineOrientation::Horizontal,
+                    &color,
+                    LineStyle::Solid,
+                );
+            }
+        }
+
+        if paint.shadow.is_some() {
+            render_context.builder.pop_all_shadows();
+        }
+    }
+
+    /// Ranges of glyphs on the same line that share a span and a font.
+    fn runs(&self) -> Vec<Range<usize>> {
+        let mut runs = Vec::new();
+        let mut start = 0;
+        while start < self.glyphs.len() {
+            let first = &self.glyphs[start];
+            let length = self.glyphs[start..]
+                .iter()
+                .take_while(|glyph| {
+                    glyph.line == first.line && glyph.span == first.span && glyph.font == first.font
+                })
+                .count();
+            runs.push(start..start + length);
+            start += length;
+        }
+        runs
+    }
+
+    /// The horizontal extent of a run of glyphs.
+    fn extent(&self, run: Range<usize>) -> (f32, f32) {
+        let glyphs = &self.glyphs[run];
+        let x0 = glyphs.iter().map(|glyph| glyph.x).fold(f32::INFINITY, f32::min);
+        let x1 = glyphs
             .iter()
-            .map(|glyph| GlyphInstance {
-                index: glyph.index,
-                point: LayoutPoint::new(glyph.point.x + origin.x, glyph.point.y + origin.y),
-            })
-            .collect::<Vec<_>>();
-        builder.push_text(info, bounds, &glyphs, font_instance_key, ColorF::BLACK, None);
+            .map(|glyph| glyph.x + glyph.advance)
+            .fold(f32::NEG_INFINITY, f32::max);
+        (x0, x1)
     }
 
+    /// The width of the widest line.
     pub fn width(&self) -> f32 {
         self.width
     }
+
+    /// The height of all lines, including the fonts' line gap.
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    pub fn lines(&self) -> &[LineMetrics] {
+        &self.lines
+    }
+
+    /// The offset of the first line's baseline from the top of the text.
+    pub fn baseline(&self) -> f32 {
+        self.lines.first().map_or(0.0, |line| line.baseline)
+    }
+
+    /// The x offset of the caret placed before the `index`th character.
+    ///
+    /// The caret sits on the side of the character's glyphs that its direction starts from,
+    /// or after the character before it if the character has no glyph, e.g. at the end of a
+    /// line.
+    pub fn caret_offset(&self, index: usize) -> f32 {
+        // Glyphs are in visual order, which bidi reordering makes differ from the order of
+        // their characters, so they are found by cluster.
+        if let Some((x0, x1, rtl)) = self.cluster_extent(index) {
+            return if rtl { x1 } else { x0 };
+        }
+        let previous = self
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.cluster)
+            .filter(|cluster| *cluster < index)
+            .max();
+        match previous.and_then(|cluster| self.cluster_extent(cluster)) {
+            Some((x0, x1, rtl)) => {
+                if rtl {
+                    x0
+                } else {
+                    x1
+                }
+            }
+            None => 0.0,
+        }
+    }
+
+    /// The horizontal extent of the glyphs produced for the `cluster`th character and
+    /// whether it is drawn right to left.
+    fn cluster_extent(&self, cluster: usize) -> Option<(f32, f32, bool)> {
+        let mut glyphs = self
+            .glyphs
+            .iter()
+            .filter(|glyph| glyph.cluster == cluster)
+            .peekable();
+        let rtl = glyphs.peek()?.level % 2 == 1;
+        let (x0, x1) = glyphs.fold((f32::INFINITY, f32::NEG_INFINITY), |(x0, x1), glyph| {
+            (x0.min(glyph.x), x1.max(glyph.x + glyph.advance))
+        });
+        Some((x0, x1, rtl))
+    }
+
+    /// The areas covered by the characters from `start` up to `end`, relative to the top left
+    /// corner of the text. A range that changes direction can cover several areas of a line.
+    pub fn selection_rects(&self, start: usize, end: usize) -> Vec<LayoutRect> {
+        let mut rects: Vec<LayoutRect> = Vec::new();
+        // The line of the previous glyph, if it was selected too.
+        let mut previous_line = None;
+        for glyph in &self.glyphs {
+            if glyph.cluster < start || glyph.cluster >= end {
+                previous_line = None;
+                continue;
+            }
+            let line = &self.lines[glyph.line];
+            match rects.last_mut() {
+                Some(rect) if previous_line == Some(glyph.line) => {
+                    rect.size.width = glyph.x + glyph.advance - rect.origin.x;
+                }
+                _ => rects.push(LayoutRect::new(
+                    LayoutPoint::new(glyph.x, line.top),
+                    LayoutSize::new(glyph.advance, line.height),
+                )),
+            }
+            previous_line = Some(glyph.line);
+        }
+        rects
+    }
+
+    /// Finds the character under `position`, given relative to the top left corner of the
+    /// text.
+    pub fn hit_test(&self, position: Position) -> Option<TextHit> {
+        let line = self
+            .lines
+            .iter()
+            .position(|line| position.y >= line.top && position.y < line.top + line.height)?;
+        self.glyphs
+            .iter()
+            .find(|glyph| {
+                glyph.line == line
+                    && position.x >= glyph.x
+                    && position.x < glyph.x + glyph.advance
+            })
+            .map(|glyph| TextHit {
+                span: glyph.span,
+                index: glyph.cluster,
+            })
+    }
+}
+
+fn trim_end(glyphs: &[ShapedGlyph]) -> &[ShapedGlyph] {
+    let end = glyphs
+        .iter()
+        .rposition(|glyph| !glyph.whitespace)
+        .map_or(0, |index| index + 1);
+    &glyphs[..end]
+}
+
+/// The width of a line, not counting trailing whitespace.
+fn line_width(glyphs: &[ShapedGlyph]) -> f32 {
+    trim_end(glyphs).iter().map(|glyph| glyph.advance).sum()
+}
+
+/// Breaks a paragraph into lines no wider than `max_width`, preferring to break after
+/// whitespace and only splitting words that don't fit on a line by themselves.
+fn wrap(glyphs: Vec<ShapedGlyph>, span: usize, max_width: f32, lines: &mut Vec<Line>) {
+    let mut line: Vec<ShapedGlyph> = Vec::new();
+    // The advance of every glyph on the line. It is only compared after a glyph that isn't
+    // whitespace, when it equals the line's width.
+    let mut width = 0.0;
+    // Where the line can be broken, the index just after the last whitespace.
+    let mut break_at = None;
+    let mut wrapped = false;
+
+    for glyph in glyphs {
+        if line.is_empty() && glyph.whitespace && wrapped {
+            // Whitespace at a wrap point is dropped rather than starting the next line.
+            continue;
+        }
+
+        line.push(glyph);
+        width += glyph.advance;
+        if glyph.whitespace {
+            break_at = Some(line.len());
+            continue;
+        }
+
+        if line.len() > 1 && width > max_width {
+            let split = break_at.unwrap_or_else(|| {
+                // Never split the glyphs of one cluster, e.g. a letter and its accent.
+                let mut split = line.len() - 1;
+                while split > 1 && line[split].cluster == line[split - 1].cluster {
+                    split -= 1;
+                }
+                split
+            });
+            let rest = line.split_off(split);
+            lines.push(Line {
+                glyphs: line,
+                span,
+                last_in_paragraph: false,
+                truncated: false,
+            });
+            line = rest
+                .into_iter()
+                .skip_while(|glyph| glyph.whitespace)
+                .collect();
+            width = line.iter().map(|glyph| glyph.advance).sum();
+            break_at = None;
+            wrapped = true;
+        }
+    }
+
+    lines.push(Line {
+        glyphs: line,
+        span,
+        last_in_paragraph: true,
+        truncated: false,
+    });
+}
+
+/// Shortens a line until it fits within `max_width` with an ellipsis appended, drawn in the
+/// primary font of the line's last span.
+fn ellipsize(registry: &FontRegistry, spans: &[SpanFonts], line: &mut Line, max_width: f32) {
+    let last = line.glyphs.last().copied();
+    let span = last.map_or(line.span, |last| last.span);
+    let font_id = spans[span].chain[0];
+    let font = registry.font(font_id);
+    let (character, count) = if font.glyph(ELLIPSIS).id() == GlyphId(0) {
+        ('.', 3)
+    } else {
+        (ELLIPSIS, 1)
+    };
+    let glyph = font.glyph(character).scaled(scale(font, spans[span].size));
+    let ellipsis = ShapedGlyph {
+        id: glyph.id().0,
+        cluster: last.map_or(0, |last| last.cluster + 1),
+        advance: glyph.h_metrics().advance_width,
+        x_offset: 0.0,
+        y_offset: 0.0,
+        whitespace: false,
+        level: last.map_or(0, |last| last.level),
+        font: font_id,
+        span,
+    };
+
+    let ellipsis_width = ellipsis.advance * count as f32;
+    while !line.glyphs.is_empty() && line_width(&line.glyphs) + ellipsis_width > max_width {
+        line.glyphs.pop();
+    }
+    let end = trim_end(&line.glyphs).len();
+    line.glyphs.truncate(end);
+    line.glyphs.extend(std::iter::repeat(ellipsis).take(count));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Three words of the same width.
+    const TEXT: &str = "aaa aaa aaa";
+    // The character index of the space after the second word.
+    const SECOND_SPACE: usize = 7;
+
+    fn layout(text: &str, paragraph: ParagraphStyle, max_width: f32) -> FinalText {
+        let registry = FontRegistry::default();
+        FinalText::new(
+            &registry,
+            &TextStyle::default(),
+            text,
+            &paragraph,
+            max_width,
+        )
+    }
+
+    fn wrapped(align: TextAlign) -> ParagraphStyle {
+        ParagraphStyle {
+            align,
+            wrap: true,
+            ..ParagraphStyle::default()
+        }
+    }
+
+    /// The width of the first two words as they are laid out within `TEXT`.
+    fn two_words_width() -> f32 {
+        layout(TEXT, ParagraphStyle::default(), f32::INFINITY).caret_offset(SECOND_SPACE)
+    }
+
+    fn first_x(text: &FinalText, line: usize) -> f32 {
+        text.glyphs
+            .iter()
+            .filter(|glyph| glyph.line == line)
+            .map(|glyph| glyph.x)
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 0.01,
+            "{} is not close to {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn wrap_fills_lines_up_to_the_width_limit() {
+        let width = two_words_width();
+
+        let text = layout(TEXT, wrapped(TextAlign::Left), width);
+        assert_eq!(text.lines().len(), 2);
+        assert_eq!(text.lines()[0].width, width);
+        assert!(text.lines()[1].width <= width);
+        // The space the line broke at is dropped from the start of the next line.
+        assert_eq!(
+            text.glyphs
+                .iter()
+                .find(|glyph| glyph.line == 1)
+                .unwrap()
+                .cluster,
+            SECOND_SPACE + 1
+        );
+
+        let text = layout(TEXT, wrapped(TextAlign::Left), width - 0.5);
+        assert_eq!(text.lines().len(), 3);
+    }
+
+    #[test]
+    fn align_places_lines_within_the_width() {
+        let text = layout(TEXT, ParagraphStyle::default(), f32::INFINITY);
+        let width = text.width();
+        let max_width = width + 100.0;
+
+        let left = layout(TEXT, wrapped(TextAlign::Left), max_width);
+        assert_close(first_x(&left, 0), 0.0);
+        let center = layout(TEXT, wrapped(TextAlign::Center), max_width);
+        assert_close(first_x(&center, 0), 50.0);
+        let right = layout(TEXT, wrapped(TextAlign::Right), max_width);
+        assert_close(first_x(&right, 0), 100.0);
+    }
+
+    #[test]
+    fn justify_stretches_all_but_the_last_line() {
+        let max_width = two_words_width() + 20.0;
+        let text = layout(TEXT, wrapped(TextAlign::Justify), max_width);
+        assert_eq!(text.lines().len(), 2);
+
+        // The space between the first two words takes up the rest of the first line.
+        let second_word_end = text
+            .glyphs
+            .iter()
+            .find(|glyph| glyph.cluster == SECOND_SPACE - 1)
+            .unwrap();
+        assert_close(second_word_end.x + second_word_end.advance, max_width);
+        assert_eq!(first_x(&text, 0), 0.0);
+        assert_eq!(first_x(&text, 1), 0.0);
+    }
+
+    /// Whether the last glyph of `text` is the ellipsis `ellipsize` appends.
+    fn ends_with_ellipsis(text: &FinalText) -> bool {
+        let registry = FontRegistry::default();
+        let last = text.glyphs.last().unwrap();
+        let font = registry.font(last.font.font);
+        let character = if font.glyph(ELLIPSIS).id() == GlyphId(0) {
+            '.'
+        } else {
+            ELLIPSIS
+        };
+        last.instance.index == font.glyph(character).id().0
+    }
+
+    #[test]
+    fn ellipsis_truncates_lines_wider_than_the_width() {
+        let width = two_words_width();
+        let paragraph = ParagraphStyle {
+            overflow: TextOverflow::Ellipsis,
+            ..ParagraphStyle::default()
+        };
+
+        let text = layout(TEXT, paragraph, width);
+        assert_eq!(text.lines().len(), 1);
+        assert!(text.lines()[0].width <= width);
+        assert!(ends_with_ellipsis(&text));
+
+        // Text that fits is left alone.
+        let text = layout(TEXT, paragraph, f32::INFINITY);
+        assert!(!ends_with_ellipsis(&text));
+    }
+
+    #[test]
+    fn ellipsis_ends_the_last_of_max_lines() {
+        let width = two_words_width();
+        let paragraph = ParagraphStyle {
+            align: TextAlign::Left,
+            wrap: true,
+            max_lines: Some(1),
+            overflow: TextOverflow::Ellipsis,
+        };
+
+        let text = layout(TEXT, paragraph, width);
+        assert_eq!(text.lines().len(), 1);
+        assert!(text.lines()[0].width <= width);
+        assert!(ends_with_ellipsis(&text));
+    }
+
+    // Two left-to-right letters, a space and two right-to-left letters, which are drawn in
+    // reverse: "ab בא".
+    const MIXED: &str = "ab \u{5d0}\u{5d1}";
+
+    #[test]
+    fn cluster_extent_reports_the_direction() {
+        let text = layout(MIXED, ParagraphStyle::default(), f32::INFINITY);
+        assert!(!text.cluster_extent(0).unwrap().2);
+        assert!(text.cluster_extent(3).unwrap().2);
+        assert!(text.cluster_extent(5).is_none());
+
+        // The first right-to-left letter is drawn at the end of the line.
+        let (_, x1, _) = text.cluster_extent(3).unwrap();
+        assert_close(x1, text.width());
+    }
+
+    #[test]
+    fn caret_follows_the_direction_of_the_text() {
+        let text = layout(MIXED, ParagraphStyle::default(), f32::INFINITY);
+        assert_eq!(text.caret_offset(0), 0.0);
+        assert!(text.caret_offset(1) < text.caret_offset(2));
+
+        // Right-to-left carets start at the right edge and move left.
+        assert_close(text.caret_offset(3), text.width());
+        assert!(text.caret_offset(4) < text.caret_offset(3));
+        assert!(text.caret_offset(5) < text.caret_offset(4));
+        // The end of the text is at the left edge of the right-to-left run, after the space.
+        assert!(text.caret_offset(5) > text.caret_offset(2));
+    }
+
+    #[test]
+    fn selection_covers_one_area_per_visual_run() {
+        let text = layout(MIXED, ParagraphStyle::default(), f32::INFINITY);
+
+        let rects = text.selection_rects(3, 5);
+        assert_eq!(rects.len(), 1);
+        assert_close(rects[0].origin.x, text.caret_offset(5));
+        assert_close(rects[0].origin.x + rects[0].size.width, text.width());
+
+        // "b", the space and the first right-to-left letter, which is drawn apart from them.
+        let rects = text.selection_rects(1, 4);
+        assert_eq!(rects.len(), 2);
+        assert_close(rects[0].origin.x, text.caret_offset(1));
+        assert_close(rects[1].origin.x + rects[1].size.width, text.width());
+    }
 }
@@ -0,0 +1,123 @@
+use crate::font::{FontWeight, TextStyle};
+use webrender::api::*;
+use webrender::api::units::*;
+
+/// The colors widgets draw with.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Palette {
+    /// Fills the window behind every widget.
+    pub background: ColorF,
+    /// Fills widgets that hold content, like text boxes.
+    pub surface: ColorF,
+    pub text: ColorF,
+    /// Fills buttons and boxes that aren't given a color of their own.
+    pub primary: ColorF,
+    /// Marks the focused widget.
+    pub accent: ColorF,
+    pub selection: ColorF,
+    pub border: ColorF,
+    pub border_hovered: ColorF,
+    pub scrollbar: ColorF,
+    pub scrollbar_hovered: ColorF,
+}
+
+/// The text styles widgets use when they aren't given one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Typography {
+    pub body: TextStyle,
+    pub heading: TextStyle,
+}
+
+/// Space left between and around widgets.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Spacing {
+    pub small: f32,
+    pub medium: f32,
+    pub large: f32,
+}
+
+/// Describes how widgets look, shared by every widget so they draw consistently.
+///
+/// The theme lives in the `World` as a resource. Widgets read it with `LayoutContext::theme`
+/// and `RenderContext::theme`, and a widget can replace it for itself and its descendants by
+/// returning its own from `Widget::theme`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub palette: Palette,
+    pub typography: Typography,
+    pub spacing: Spacing,
+    pub corner_radius: f32,
+    /// The shadow cast by raised widgets, e.g. a hovered button.
+    pub shadow: Shadow,
+}
+
+impl Theme {
+    pub fn light() -> Theme {
+        Theme {
+            palette: Palette {
+                background: ColorF::new(0.98, 0.98, 0.98, 1.0),
+                surface: ColorF::WHITE,
+                text: ColorF::BLACK,
+                primary: ColorF::new(0.2, 0.5, 0.9, 1.0),
+                accent: ColorF::new(0.2, 0.5, 0.9, 0.8),
+                selection: ColorF::new(0.6, 0.8, 1.0, 1.0),
+                border: ColorF::new(0.7, 0.7, 0.7, 1.0),
+                border_hovered: ColorF::new(0.5, 0.5, 0.5, 1.0),
+                scrollbar: ColorF::new(0.0, 0.0, 0.0, 0.3),
+                scrollbar_hovered: ColorF::new(0.0, 0.0, 0.0, 0.5),
+            },
+            typography: Typography::default(),
+            spacing: Spacing {
+                small: 4.0,
+                medium: 10.0,
+                large: 16.0,
+            },
+            corner_radius: 4.0,
+            shadow: Shadow {
+                offset: LayoutVector2D::new(0.0, 3.0),
+                color: ColorF::new(0.0, 0.0, 0.0, 0.2),
+                blur_radius: 4.0,
+            },
+        }
+    }
+
+    pub fn dark() -> Theme {
+        let light = Theme::light();
+        Theme {
+            palette: Palette {
+                background: ColorF::new(0.12, 0.12, 0.13, 1.0),
+                surface: ColorF::new(0.18, 0.18, 0.2, 1.0),
+                text: ColorF::new(0.92, 0.92, 0.92, 1.0),
+                primary: ColorF::new(0.3, 0.55, 0.95, 1.0),
+                accent: ColorF::new(0.4, 0.65, 1.0, 0.8),
+                selection: ColorF::new(0.25, 0.4, 0.6, 1.0),
+                border: ColorF::new(0.35, 0.35, 0.38, 1.0),
+                border_hovered: ColorF::new(0.55, 0.55, 0.58, 1.0),
+                scrollbar: ColorF::new(1.0, 1.0, 1.0, 0.3),
+                scrollbar_hovered: ColorF::new(1.0, 1.0, 1.0, 0.5),
+            },
+            shadow: Shadow {
+                color: ColorF::new(0.0, 0.0, 0.0, 0.5),
+                ..light.shadow
+            },
+            ..light
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::light()
+    }
+}
+
+impl Default for Typography {
+    fn default() -> Typography {
+        Typography {
+            body: TextStyle::default(),
+            heading: TextStyle::default()
+                .with_size(48.0)
+                .with_weight(FontWeight::BOLD),
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{animation::Clock, Message, MessageQueue};
+use specs::World;
+use std::{
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Identifies a timer scheduled with `WidgetContext::set_timeout` or
+/// `WidgetContext::set_interval`, to cancel it.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TimerId(u64);
+
+enum TimerMessage<M: Message> {
+    Once(M),
+    Repeat(Box<dyn Fn() -> M + Send + Sync + 'static>),
+}
+
+struct Timer<M: Message> {
+    id: TimerId,
+    // Measured on the same clock as animations.
+    due: Duration,
+    interval: Duration,
+    message: TimerMessage<M>,
+}
+
+/// The timers scheduled by the application, which enqueue their messages when they are due.
+pub(crate) struct Timers<M: Message> {
+    timers: Vec<Timer<M>>,
+    next_id: u64,
+}
+
+impl<M: Message> Default for Timers<M> {
+    fn default() -> Timers<M> {
+        Timers {
+            timers: Vec::new(),
+            next_id: 0,
+        }
+    }
+}
+
+impl<M: Message> Timers<M> {
+    pub(crate) fn once(&mut self, due: Duration, message: M) -> TimerId {
+        self.schedule(due, Duration::default(), TimerMessage::Once(message))
+    }
+
+    pub(crate) fn repeat<F>(&mut self, due: Duration, interval: Duration, message: F) -> TimerId
+    where
+        F: Fn() -> M + Send + Sync + 'static,
+    {
+        // An interval of zero would fire on every frame without ever letting time pass.
+        let interval = interval.max(Duration::from_millis(1));
+        self.schedule(due, interval, TimerMessage::Repeat(Box::new(message)))
+    }
+
+    fn schedule(&mut self, due: Duration, interval: Duration, message: TimerMessage<M>) -> TimerId {
+        let id = TimerId(self.next_id);
+        self.next_id += 1;
+        self.timers.push(Timer {
+            id,
+            due,
+            interval,
+            message,
+        });
+        id
+    }
+
+    pub(crate) fn cancel(&mut self, id: TimerId) {
+        self.timers.retain(|timer| timer.id != id);
+    }
+
+    /// When the earliest timer is due.
+    pub(crate) fn next_due(&self) -> Option<Duration> {
+        self.timers.iter().map(|timer| timer.due).min()
+    }
+
+    /// Removes the one-shot timers due at `now` and reschedules the repeating ones, returning
+    /// their messages in the order they were due.
+    fn take_due(&mut self, now: Duration) -> Vec<M> {
+        let mut due = Vec::new();
+        let mut index = 0;
+        while index < self.timers.len() {
+            if self.timers[index].due > now {
+                index += 1;
+                continue;
+            }
+            let timer = &mut self.timers[index];
+            let timer_due = timer.due;
+            match &timer.message {
+                TimerMessage::Repeat(message) => {
+                    due.push((timer_due, message()));
+                    // A repeating timer that fell behind fires once and skips the intervals
+                    // it missed rather than firing for each of them.
+                    while timer.due <= now {
+                        timer.due += timer.interval;
+                    }
+                    index += 1;
+                }
+                TimerMessage::Once(_) => {
+                    if let TimerMessage::Once(message) = self.timers.remove(index).message {
+                        due.push((timer_due, message));
+                    }
+                }
+            }
+        }
+        due.sort_by_key(|(timer_due, _)| *timer_due);
+        due.into_iter().map(|(_, message)| message).collect()
+    }
+}
+
+/// Enqueues the messages of the timers that are due, returning whether any were.
+pub(crate) fn fire_timers<M: Message>(world: &World) -> bool {
+    let now = world.read_resource::<Clock>().now;
+    let messages = world.write_resource::<Timers<M>>().take_due(now);
+    if messages.is_empty() {
+        return false;
+    }
+    world
+        .write_resource::<MessageQueue<M>>()
+        .0
+        .extend(messages.into_iter().map(|message| (None, message)));
+    true
+}
+
+/// Wakes the event loop when the next timer is due, which it otherwise only does for input.
+pub(crate) struct TimerWaker {
+    sender: mpsc::Sender<Option<Instant>>,
+    deadline: Option<Instant>,
+}
+
+impl TimerWaker {
+    pub(crate) fn new(events_proxy: glutin::EventsLoopProxy) -> TimerWaker {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut deadline: Option<Instant> = None;
+            loop {
+                let received = match deadline {
+                    Some(instant) => {
+                        receiver.recv_timeout(instant.saturating_duration_since(Instant::now()))
+                    }
+                    None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                };
+                match received {
+                    Ok(next) => deadline = next,
+                    Err(RecvTimeoutError::Timeout) => {
+                        deadline = None;
+                        if events_proxy.wakeup().is_err() {
+                            return;
+                        }
+                    }
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+        });
+        TimerWaker {
+            sender,
+            deadline: None,
+        }
+    }
+
+    /// Schedules the wakeup for `deadline`, replacing the previous one.
+    pub(crate) fn wake_at(&mut self, deadline: Option<Instant>) {
+        if deadline != self.deadline {
+            self.deadline = deadline;
+            self.sender.send(deadline).ok();
+        }
+    }
+}
@@ -1,13 +1,28 @@
 use crate::{
-    interactive::Interaction, text::FinalText, BoxConstraint, Geometry, LayoutContext, Message,
-    RenderContext, Size, WidgetContext,
+    interactive::{DragPayload, Interaction, KeyEvent},
+    style::short_type_name,
+    text::FinalText,
+    BoxConstraint, Geometry, LayoutContext, Message, Position, RenderContext, Size, Theme,
+    WidgetContext,
 };
 use specs::{Component, DenseVecStorage, Entity};
-use std::any::Any;
+use std::{any::Any, time::Duration};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct WidgetId(pub(crate) Entity);
 
+/// What has to be redone after a widget changed, ordered from least to most work.
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Invalidation {
+    /// Nothing the widget shows changed.
+    None,
+    /// The widget has to be rendered again, but its size and the positions of its children
+    /// stay the same.
+    Paint,
+    /// The widget has to be laid out again, which may also lay out its ancestors.
+    Layout,
+}
+
 pub trait WidgetBuilder {
     fn build<T: Message>(self, context: &mut WidgetContext<T>) -> WidgetId;
 }
@@ -22,6 +37,17 @@ pub trait Widget: Send + Sync {
 
     fn children(&self) -> Vec<WidgetId>;
 
+    /// The name style sheet selectors match this widget's type by, the type's name without
+    /// its module path by default.
+    fn type_name(&self) -> &str {
+        short_type_name(std::any::type_name::<Self>())
+    }
+
+    /// Pushes the widget's display items with `render_context.builder`, returning the hit test
+    /// tag of an interactive widget.
+    ///
+    /// The items are replayed in later frames without calling `render` until the widget is
+    /// laid out again or an interaction or animation invalidates its paint.
     fn render(
         &self,
         _id: WidgetId,
@@ -32,8 +58,103 @@ pub trait Widget: Send + Sync {
         None
     }
 
+    /// The area outside of which this widget's children are hidden, given the widget's own
+    /// geometry. Hit testing respects it, so it should match the clip `render` leaves for the
+    /// children.
+    fn children_clip(&self, _geometry: Geometry) -> Option<Geometry> {
+        None
+    }
+
+    /// A theme that replaces the current one while this widget and its descendants are laid
+    /// out and rendered. The descendants are laid out again when `update` changes it.
+    fn theme(&self) -> Option<&Theme> {
+        None
+    }
+
+    /// Whether this widget receives `Interaction::Wheel` when the wheel is used over it.
+    fn scrollable(&self) -> bool {
+        false
+    }
+
+    /// Whether this widget can receive keyboard focus by clicking or with Tab.
+    fn focusable(&self) -> bool {
+        false
+    }
+
+    /// Whether pressing Enter or Space while this widget is focused activates its
+    /// `ClickListener`.
+    fn activatable(&self) -> bool {
+        false
+    }
+
+    /// Whether this widget, while focused, uses `key_event` itself, which keeps the
+    /// application's key bindings from handling it. Ctrl or Cmd with C, X or V is sent as a
+    /// clipboard command instead of a key press if the widget uses it.
+    fn handles_key(&self, _key_event: KeyEvent) -> bool {
+        false
+    }
+
     fn handle_interaction(&mut self, _interaction: Interaction) {}
 
+    /// What handling `interaction` changed, asked after `handle_interaction` and, for clipboard
+    /// commands, after `copy`, `cut` or `paste`. Widgets
+    /// whose layout does not depend on e.g. being hovered return `Invalidation::Paint` for it,
+    /// so hovering them does not lay the window out again.
+    fn interaction_invalidation(&self, _interaction: Interaction) -> Invalidation {
+        Invalidation::Layout
+    }
+
+    /// Text placed on the clipboard when the user copies while this widget is focused.
+    fn copy(&self) -> Option<String> {
+        None
+    }
+
+    /// Like `copy`, but the widget should also remove the text it returns.
+    fn cut(&mut self) -> Option<String> {
+        None
+    }
+
+    /// Called with the clipboard contents when the user pastes while this widget is focused.
+    fn paste(&mut self, _text: &str) {}
+
+    /// Called when the pointer is dragged away from a press, on the pressed widget and then on
+    /// each of its ancestors until one returns a payload to start a drag-and-drop.
+    ///
+    /// `pressed` is this widget if it was pressed, otherwise the child of this widget that
+    /// contains the pressed widget.
+    fn start_drag(&mut self, _pressed: WidgetId) -> Option<DragPayload> {
+        None
+    }
+
+    /// Whether dragging away from a press on this widget or its descendants moves this widget
+    /// instead of starting a drag-and-drop, e.g. to scroll. Asked along with `start_drag`, and
+    /// a widget that claims the drag receives the `MouseMove`s until the button is released.
+    fn claims_drag(&self) -> bool {
+        false
+    }
+
+    /// Whether this widget can receive `payload` when it is dropped on it.
+    fn accepts_drop(&self, _payload: &dyn Any) -> bool {
+        false
+    }
+
+    /// Receives the payload of a drag released over this widget, at `position` relative to
+    /// the widget's geometry.
+    fn drop_payload(&mut self, _payload: DragPayload, _position: Position) {}
+
+    /// Advances the widget's animations to `now`, e.g. with `Animated::tick`, returning what
+    /// their change invalidated. Frames keep being rendered until it returns
+    /// `Invalidation::None`.
+    fn animate(&mut self, _now: Duration) -> Invalidation {
+        Invalidation::None
+    }
+
+    /// Events produced while handling interactions, which are passed to the widget's
+    /// `EventListener` after every frame.
+    fn take_events(&mut self) -> Vec<Box<dyn Any>> {
+        Vec::new()
+    }
+
     fn update(&mut self, _event: Box<dyn Any>) -> Option<Vec<WidgetId>> {
         None
     }
@@ -41,6 +162,8 @@ pub trait Widget: Send + Sync {
 
 pub(crate) struct WidgetComponent {
     pub(crate) inner: Box<dyn Widget>,
+    /// Classes that style sheet selectors match.
+    pub(crate) classes: Vec<String>,
 }
 
 impl std::ops::Deref for WidgetComponent {
@@ -0,0 +1,230 @@
+use crate::{
+    widget::WidgetComponent, Message, Position, Size, WidgetContext, WidgetId, WindowComponent,
+};
+use specs::{storage::MaskedStorage, Entity, Join, ReadStorage, Storage, World};
+use std::{collections::HashMap, ops::Deref};
+
+/// Identifies a window, e.g. to close it or to tell which window a message came from.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct WindowId(pub(crate) Entity);
+
+/// The properties of a window the application can change with a `WindowHandle`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WindowAttributes {
+    pub title: String,
+    /// The size of the window's contents.
+    pub size: Size,
+    pub min_size: Option<Size>,
+    pub max_size: Option<Size>,
+    pub resizable: bool,
+    pub fullscreen: bool,
+    pub always_on_top: bool,
+    /// Whether the window has a title bar and borders.
+    pub decorations: bool,
+    /// Where the window is on the desktop, left to the system if unset.
+    pub position: Option<Position>,
+}
+
+impl WindowAttributes {
+    pub fn new<T: Into<String>>(title: T, size: Size) -> WindowAttributes {
+        WindowAttributes {
+            title: title.into(),
+            size,
+            min_size: None,
+            max_size: None,
+            resizable: true,
+            fullscreen: false,
+            always_on_top: false,
+            decorations: true,
+            position: None,
+        }
+    }
+
+    pub(crate) fn apply(&mut self, command: &WindowCommand) {
+        match command {
+            WindowCommand::SetTitle(title) => self.title = title.clone(),
+            WindowCommand::SetSize(size) => self.size = *size,
+            WindowCommand::SetMinSize(size) => self.min_size = *size,
+            WindowCommand::SetMaxSize(size) => self.max_size = *size,
+            WindowCommand::SetResizable(resizable) => self.resizable = *resizable,
+            WindowCommand::SetFullscreen(fullscreen) => self.fullscreen = *fullscreen,
+            WindowCommand::SetAlwaysOnTop(always_on_top) => self.always_on_top = *always_on_top,
+            WindowCommand::SetDecorations(decorations) => self.decorations = *decorations,
+            WindowCommand::SetPosition(position) => self.position = Some(*position),
+        }
+    }
+}
+
+/// Changes to a window reported to `Application::window_event`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WindowEvent {
+    Focused(bool),
+    /// The window moved to a position on the desktop.
+    Moved(Position),
+    Resized(Size),
+    /// The window was minimized. Systems report this as a resize to nothing, so `Resized`
+    /// follows when the window is restored.
+    Minimized,
+    /// The number of physical pixels per logical pixel changed, e.g. because the window
+    /// moved to another monitor.
+    DpiChanged(f32),
+}
+
+/// Window events that have yet to be passed to the application.
+#[derive(Default)]
+pub(crate) struct WindowEvents(pub(crate) Vec<(WindowId, WindowEvent)>);
+
+pub(crate) enum WindowCommand {
+    SetTitle(String),
+    SetSize(Size),
+    SetMinSize(Option<Size>),
+    SetMaxSize(Option<Size>),
+    SetResizable(bool),
+    SetFullscreen(bool),
+    SetAlwaysOnTop(bool),
+    SetDecorations(bool),
+    SetPosition(Position),
+}
+
+/// Changes a window, returned by `WidgetContext::window_handle`.
+///
+/// Changes are applied by the event loop once the current messages are handled.
+pub struct WindowHandle<'a, M: Message> {
+    window: WindowId,
+    requests: &'a mut WindowRequests<M>,
+}
+
+impl<'a, M: Message> WindowHandle<'a, M> {
+    pub(crate) fn new(window: WindowId, requests: &'a mut WindowRequests<M>) -> Self {
+        WindowHandle { window, requests }
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window
+    }
+
+    pub fn set_title<T: Into<String>>(&mut self, title: T) -> &mut Self {
+        self.push(WindowCommand::SetTitle(title.into()))
+    }
+
+    pub fn set_size(&mut self, size: Size) -> &mut Self {
+        self.push(WindowCommand::SetSize(size))
+    }
+
+    pub fn set_min_size(&mut self, size: Option<Size>) -> &mut Self {
+        self.push(WindowCommand::SetMinSize(size))
+    }
+
+    pub fn set_max_size(&mut self, size: Option<Size>) -> &mut Self {
+        self.push(WindowCommand::SetMaxSize(size))
+    }
+
+    pub fn set_resizable(&mut self, resizable: bool) -> &mut Self {
+        self.push(WindowCommand::SetResizable(resizable))
+    }
+
+    /// Makes the window cover the monitor it is on, or restores it.
+    pub fn set_fullscreen(&mut self, fullscreen: bool) -> &mut Self {
+        self.push(WindowCommand::SetFullscreen(fullscreen))
+    }
+
+    pub fn set_always_on_top(&mut self, always_on_top: bool) -> &mut Self {
+        self.push(WindowCommand::SetAlwaysOnTop(always_on_top))
+    }
+
+    pub fn set_decorations(&mut self, decorations: bool) -> &mut Self {
+        self.push(WindowCommand::SetDecorations(decorations))
+    }
+
+    pub fn set_position(&mut self, position: Position) -> &mut Self {
+        self.push(WindowCommand::SetPosition(position))
+    }
+
+    fn push(&mut self, command: WindowCommand) -> &mut Self {
+        self.requests
+            .0
+            .push(WindowRequest::Update(self.window, command));
+        self
+    }
+}
+
+pub(crate) type BuildWindow<M> =
+    Box<dyn FnOnce(&mut WidgetContext<M>) -> WidgetId + Send + Sync + 'static>;
+
+pub(crate) enum WindowRequest<M: Message> {
+    Open {
+        window: WindowId,
+        attributes: WindowAttributes,
+        build: BuildWindow<M>,
+    },
+    Update(WindowId, WindowCommand),
+    Close(WindowId),
+    /// Closes every window, which ends the event loop.
+    Quit,
+}
+
+/// Windows opened, changed and closed by the application, which the event loop applies
+/// once the current messages are handled.
+pub(crate) struct WindowRequests<M: Message>(pub(crate) Vec<WindowRequest<M>>);
+
+impl<M: Message> Default for WindowRequests<M> {
+    fn default() -> WindowRequests<M> {
+        WindowRequests(Vec::new())
+    }
+}
+
+/// Maps every widget to the window whose tree contains it.
+pub(crate) fn widget_windows<D>(
+    windows: &ReadStorage<WindowComponent>,
+    widgets: &Storage<WidgetComponent, D>,
+) -> HashMap<Entity, WindowId>
+where
+    D: Deref<Target = MaskedStorage<WidgetComponent>>,
+{
+    fn visit<D>(
+        widget_id: WidgetId,
+        window: WindowId,
+        widgets: &Storage<WidgetComponent, D>,
+        owners: &mut HashMap<Entity, WindowId>,
+    ) where
+        D: Deref<Target = MaskedStorage<WidgetComponent>>,
+    {
+        owners.insert(widget_id.0, window);
+        if let Some(widget) = widgets.get(widget_id.0) {
+            for child in widget.children() {
+                visit(child, window, widgets, owners);
+            }
+        }
+    }
+
+    let entities = windows.fetched_entities();
+    let mut owners = HashMap::new();
+    for (entity, window) in (entities, windows).join() {
+        visit(window.root, WindowId(entity), widgets, &mut owners);
+    }
+    owners
+}
+
+/// Deletes a window's entity together with every widget in its tree.
+pub(crate) fn remove_window(world: &World, window: WindowId) {
+    fn remove_widgets(world: &World, widget_id: WidgetId) {
+        let children = world
+            .read_storage::<WidgetComponent>()
+            .get(widget_id.0)
+            .map(|widget| widget.children())
+            .unwrap_or_default();
+        for child in children {
+            remove_widgets(world, child);
+        }
+        world.entities().delete(widget_id.0).ok();
+    }
+
+    let root = world
+        .read_storage::<WindowComponent>()
+        .get(window.0)
+        .map(|window_component| window_component.root);
+    if let Some(root) = root {
+        remove_widgets(world, root);
+    }
+    world.entities().delete(window.0).ok();
+}
@@ -0,0 +1,104 @@
+use imagine::{
+    text::FinalText, Animated, Application, BoxConstraint, ColorF, Easing, Geometry, Headless,
+    Interaction, Invalidation, LayoutContext, Position, RenderContext, Size, Widget, WidgetContext,
+    WidgetId,
+};
+use std::time::Duration;
+use webrender::api::units::*;
+use webrender::api::*;
+
+const FADE: Duration = Duration::from_millis(100);
+
+/// A square that fades in a highlight while hovered.
+struct Swatch {
+    highlight: Animated<f32>,
+}
+
+impl Widget for Swatch {
+    fn layout(
+        &self,
+        _id: WidgetId,
+        _layout_context: &mut LayoutContext,
+        box_constraint: BoxConstraint,
+    ) -> Size {
+        box_constraint.constrain(Size::new(50.0, 50.0))
+    }
+
+    fn children(&self) -> Vec<WidgetId> {
+        vec![]
+    }
+
+    fn handle_interaction(&mut self, interaction: Interaction) {
+        if let Interaction::Hovered(hovered) = interaction {
+            self.highlight.set(if hovered { 1.0 } else { 0.0 });
+        }
+    }
+
+    fn interaction_invalidation(&self, _interaction: Interaction) -> Invalidation {
+        Invalidation::Paint
+    }
+
+    fn animate(&mut self, now: Duration) -> Invalidation {
+        if self.highlight.tick(now) {
+            Invalidation::Paint
+        } else {
+            Invalidation::None
+        }
+    }
+
+    fn render(
+        &self,
+        _id: WidgetId,
+        geometry: Geometry,
+        _text: Option<&FinalText>,
+        render_context: &mut RenderContext,
+    ) -> Option<u64> {
+        let rect = LayoutRect::new(
+            LayoutPoint::new(geometry.position.x, geometry.position.y),
+            LayoutSize::new(geometry.size.width, geometry.size.height),
+        );
+        let identifier = render_context.next_tag_identifier();
+        render_context.builder.push_rect(
+            &CommonItemProperties::new(rect, render_context.current_space_and_clip),
+            ColorF::new(self.highlight.get(), 0.0, 0.0, 1.0),
+        );
+        Some(identifier)
+    }
+}
+
+struct Fading;
+
+impl Application for Fading {
+    type Message = ();
+
+    fn build(&mut self, context: &mut WidgetContext<()>) -> WidgetId {
+        context.create_widget(Swatch {
+            highlight: Animated::new(0.0, FADE, Easing::Linear),
+        })
+    }
+}
+
+#[test]
+fn advance_time_runs_tweens_until_they_finish() {
+    let mut headless = Headless::new(Fading, Size::new(50.0, 50.0));
+    headless.step();
+    assert!(!headless.is_animating());
+
+    headless.move_cursor(Position::new(25.0, 25.0));
+    headless.advance_time(FADE / 2);
+    assert!(headless.is_animating());
+    // Only the animated widget is painted again.
+    assert_eq!(headless.layout_stats().laid_out, 0);
+    assert_eq!(headless.paint_stats().painted, 1);
+
+    // However long the first frame was delayed, the tween reached its target by now, and the
+    // frame after that has nothing left to animate.
+    headless.advance_time(FADE);
+    headless.advance_time(Duration::from_millis(16));
+    assert!(!headless.is_animating());
+
+    // Moving away fades the highlight out again.
+    headless.move_cursor(Position::new(100.0, 100.0));
+    headless.advance_time(FADE / 2);
+    assert!(headless.is_animating());
+}
@@ -0,0 +1,132 @@
+use imagine::{
+    text::FinalText, Application, BoxConstraint, ClickListener, Geometry, Headless, LayoutContext,
+    MouseButton, Position, RenderContext, Size, Widget, WidgetContext, WidgetId,
+};
+
+const BUTTON_SIZE: f32 = 50.0;
+
+/// A square that can be clicked.
+struct Square;
+
+impl Widget for Square {
+    fn layout(
+        &self,
+        _id: WidgetId,
+        _layout_context: &mut LayoutContext,
+        box_constraint: BoxConstraint,
+    ) -> Size {
+        box_constraint.constrain(Size::new(BUTTON_SIZE, BUTTON_SIZE))
+    }
+
+    fn children(&self) -> Vec<WidgetId> {
+        vec![]
+    }
+
+    fn render(
+        &self,
+        _id: WidgetId,
+        _geometry: Geometry,
+        _text: Option<&FinalText>,
+        render_context: &mut RenderContext,
+    ) -> Option<u64> {
+        Some(render_context.next_tag_identifier())
+    }
+}
+
+/// Places its children side by side, `BUTTON_SIZE` apart from each other.
+struct Row {
+    children: Vec<WidgetId>,
+}
+
+impl Widget for Row {
+    fn layout(
+        &self,
+        _id: WidgetId,
+        layout_context: &mut LayoutContext,
+        box_constraint: BoxConstraint,
+    ) -> Size {
+        for (index, &child) in self.children.iter().enumerate() {
+            layout_context
+                .layout_widget(child, BoxConstraint::new(Size::zero(), box_constraint.max));
+            layout_context
+                .set_position(child, Position::new(index as f32 * BUTTON_SIZE * 2.0, 0.0));
+        }
+        box_constraint.max
+    }
+
+    fn children(&self) -> Vec<WidgetId> {
+        self.children.clone()
+    }
+}
+
+#[derive(Default)]
+struct Buttons {
+    clicked: Vec<usize>,
+}
+
+impl Application for Buttons {
+    type Message = usize;
+
+    fn build(&mut self, context: &mut WidgetContext<usize>) -> WidgetId {
+        let children = (0..2)
+            .map(|index| {
+                let square = context.create_widget(Square);
+                context.add_click_listener(square, ClickListener::new(move || index));
+                square
+            })
+            .collect();
+        context.create_widget(Row { children })
+    }
+
+    fn handle_message(&mut self, index: usize, _context: &mut WidgetContext<usize>) {
+        self.clicked.push(index);
+    }
+}
+
+fn buttons<'a, 'b>() -> Headless<'a, 'b, Buttons> {
+    let mut headless = Headless::new(Buttons::default(), Size::new(400.0, 300.0));
+    headless.step();
+    headless
+}
+
+/// The center of the square at `index`.
+fn center(index: usize) -> Position {
+    Position::new(
+        index as f32 * BUTTON_SIZE * 2.0 + BUTTON_SIZE / 2.0,
+        BUTTON_SIZE / 2.0,
+    )
+}
+
+#[test]
+fn click_sends_the_listener_message() {
+    let mut headless = buttons();
+    headless.click(center(1));
+    headless.click(center(0));
+    assert_eq!(headless.application().clicked, vec![1, 0]);
+}
+
+#[test]
+fn press_alone_is_not_a_click() {
+    let mut headless = buttons();
+    headless.move_cursor(center(0));
+    headless.mouse_down(MouseButton::Left);
+    assert!(headless.application().clicked.is_empty());
+
+    headless.mouse_up(MouseButton::Left);
+    assert_eq!(headless.application().clicked, vec![0]);
+}
+
+#[test]
+fn release_over_another_widget_is_not_a_click() {
+    let mut headless = buttons();
+    headless.drag(center(0), center(1));
+    assert!(headless.application().clicked.is_empty());
+
+    // Neither is a press dragged away and back.
+    headless.move_cursor(center(0));
+    headless.mouse_down(MouseButton::Left);
+    headless.move_cursor(center(1));
+    headless.move_cursor(center(0));
+    headless.mouse_up(MouseButton::Left);
+    assert!(headless.application().clicked.is_empty());
+}
@@ -0,0 +1,180 @@
+use imagine::{
+    Application, BoxConstraint, Headless, Interaction, LayoutContext, ModifiersState, Size,
+    VirtualKeyCode, Widget, WidgetContext, WidgetId,
+};
+use std::sync::{Arc, Mutex};
+
+/// A focus change seen by the field at an index. Lost sorts before Gained, which is the
+/// order a single Tab press sends them in.
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum Focus {
+    Lost(usize),
+    Gained(usize),
+}
+
+type Log = Arc<Mutex<Vec<Focus>>>;
+
+/// A focusable widget that logs its focus changes.
+struct Field {
+    index: usize,
+    log: Log,
+}
+
+impl Widget for Field {
+    fn layout(
+        &self,
+        _id: WidgetId,
+        _layout_context: &mut LayoutContext,
+        box_constraint: BoxConstraint,
+    ) -> Size {
+        box_constraint.constrain(Size::new(100.0, 20.0))
+    }
+
+    fn children(&self) -> Vec<WidgetId> {
+        vec![]
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn handle_interaction(&mut self, interaction: Interaction) {
+        let focus = match interaction {
+            Interaction::FocusGained => Focus::Gained(self.index),
+            Interaction::FocusLost => Focus::Lost(self.index),
+            _ => return,
+        };
+        self.log.lock().unwrap().push(focus);
+    }
+}
+
+/// A widget Tab skips over.
+struct Spacer;
+
+impl Widget for Spacer {
+    fn layout(
+        &self,
+        _id: WidgetId,
+        _layout_context: &mut LayoutContext,
+        box_constraint: BoxConstraint,
+    ) -> Size {
+        box_constraint.constrain(Size::new(100.0, 20.0))
+    }
+
+    fn children(&self) -> Vec<WidgetId> {
+        vec![]
+    }
+}
+
+struct Group {
+    children: Vec<WidgetId>,
+}
+
+impl Widget for Group {
+    fn layout(
+        &self,
+        _id: WidgetId,
+        layout_context: &mut LayoutContext,
+        box_constraint: BoxConstraint,
+    ) -> Size {
+        for &child in &self.children {
+            layout_context
+                .layout_widget(child, BoxConstraint::new(Size::zero(), box_constraint.max));
+        }
+        box_constraint.max
+    }
+
+    fn children(&self) -> Vec<WidgetId> {
+        self.children.clone()
+    }
+}
+
+#[derive(Default)]
+struct Form {
+    fields: Vec<WidgetId>,
+    log: Log,
+}
+
+impl Application for Form {
+    type Message = ();
+
+    fn build(&mut self, context: &mut WidgetContext<()>) -> WidgetId {
+        let mut children = vec![];
+        for index in 0..3 {
+            let field = context.create_widget(Field {
+                index,
+                log: self.log.clone(),
+            });
+            self.fields.push(field);
+            children.push(field);
+            children.push(context.create_widget(Spacer));
+        }
+        context.create_widget(Group { children })
+    }
+}
+
+/// Presses `key` and returns the focus changes it caused, sorted by kind since widgets
+/// handle their interactions in no particular order within a frame.
+fn press(headless: &mut Headless<Form>, key: VirtualKeyCode) -> Vec<Focus> {
+    headless.press_key(key);
+    let mut changes = headless.application().log.lock().unwrap().split_off(0);
+    changes.sort();
+    changes
+}
+
+#[test]
+fn tab_moves_focus_through_focusable_widgets() {
+    let mut headless = Headless::new(Form::default(), Size::new(400.0, 300.0));
+    headless.step();
+    let fields = headless.application().fields.clone();
+    assert_eq!(headless.focused(), None);
+
+    assert_eq!(
+        press(&mut headless, VirtualKeyCode::Tab),
+        vec![Focus::Gained(0)]
+    );
+    assert_eq!(headless.focused(), Some(fields[0]));
+    assert_eq!(
+        press(&mut headless, VirtualKeyCode::Tab),
+        vec![Focus::Lost(0), Focus::Gained(1)]
+    );
+    assert_eq!(
+        press(&mut headless, VirtualKeyCode::Tab),
+        vec![Focus::Lost(1), Focus::Gained(2)]
+    );
+    // Focus wraps around after the last focusable widget.
+    assert_eq!(
+        press(&mut headless, VirtualKeyCode::Tab),
+        vec![Focus::Lost(2), Focus::Gained(0)]
+    );
+    assert_eq!(headless.focused(), Some(fields[0]));
+}
+
+#[test]
+fn shift_tab_moves_focus_backwards() {
+    let mut headless = Headless::new(Form::default(), Size::new(400.0, 300.0));
+    headless.step();
+    let fields = headless.application().fields.clone();
+    headless.set_modifiers(ModifiersState {
+        shift: true,
+        ..ModifiersState::default()
+    });
+
+    assert_eq!(
+        press(&mut headless, VirtualKeyCode::Tab),
+        vec![Focus::Gained(2)]
+    );
+    assert_eq!(
+        press(&mut headless, VirtualKeyCode::Tab),
+        vec![Focus::Lost(2), Focus::Gained(1)]
+    );
+    assert_eq!(
+        press(&mut headless, VirtualKeyCode::Tab),
+        vec![Focus::Lost(1), Focus::Gained(0)]
+    );
+    assert_eq!(
+        press(&mut headless, VirtualKeyCode::Tab),
+        vec![Focus::Lost(0), Focus::Gained(2)]
+    );
+    assert_eq!(headless.focused(), Some(fields[2]));
+}
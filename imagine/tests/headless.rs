@@ -0,0 +1,96 @@
+use imagine::{
+    Application, BoxConstraint, Geometry, Headless, LayoutContext, Position, Size, Widget,
+    WidgetContext, WidgetId,
+};
+
+/// A leaf widget of a fixed size.
+struct Block {
+    size: Size,
+}
+
+impl Widget for Block {
+    fn layout(
+        &self,
+        _id: WidgetId,
+        _layout_context: &mut LayoutContext,
+        box_constraint: BoxConstraint,
+    ) -> Size {
+        box_constraint.constrain(self.size)
+    }
+
+    fn children(&self) -> Vec<WidgetId> {
+        vec![]
+    }
+}
+
+/// Stacks its children from top to bottom.
+struct Column {
+    children: Vec<WidgetId>,
+}
+
+impl Widget for Column {
+    fn layout(
+        &self,
+        _id: WidgetId,
+        layout_context: &mut LayoutContext,
+        box_constraint: BoxConstraint,
+    ) -> Size {
+        let mut y = 0.0;
+        for &child in &self.children {
+            let size = layout_context
+                .layout_widget(child, BoxConstraint::new(Size::zero(), box_constraint.max));
+            layout_context.set_position(child, Position::new(0.0, y));
+            y += size.height;
+        }
+        box_constraint.max
+    }
+
+    fn children(&self) -> Vec<WidgetId> {
+        self.children.clone()
+    }
+}
+
+#[derive(Default)]
+struct Blocks {
+    children: Vec<WidgetId>,
+}
+
+impl Application for Blocks {
+    type Message = ();
+
+    fn build(&mut self, context: &mut WidgetContext<()>) -> WidgetId {
+        self.children = vec![
+            context.create_widget(Block {
+                size: Size::new(100.0, 20.0),
+            }),
+            context.create_widget(Block {
+                size: Size::new(50.0, 30.0),
+            }),
+        ];
+        context.create_widget(Column {
+            children: self.children.clone(),
+        })
+    }
+}
+
+#[test]
+fn step_lays_out_and_renders_the_widget_tree() {
+    let mut headless = Headless::new(Blocks::default(), Size::new(400.0, 300.0));
+    assert!(headless.display_list().is_none());
+
+    headless.step();
+    assert!(headless.display_list().is_some());
+
+    let children = headless.application().children.clone();
+    assert_eq!(
+        headless.geometry(children[0]),
+        Some(Geometry::new(Position::zero(), Size::new(100.0, 20.0)))
+    );
+    assert_eq!(
+        headless.geometry(children[1]),
+        Some(Geometry::new(
+            Position::new(0.0, 20.0),
+            Size::new(50.0, 30.0)
+        ))
+    );
+}
@@ -0,0 +1,179 @@
+use imagine::{
+    text::FinalText, Application, BoxConstraint, Geometry, Headless, Interaction, Invalidation,
+    LayoutContext, LayoutStats, Position, RenderContext, Size, Theme, Widget, WidgetContext,
+    WidgetId,
+};
+use std::any::Any;
+
+/// A square whose layout depends on whether it is hovered.
+struct Leaf;
+
+impl Widget for Leaf {
+    fn layout(
+        &self,
+        _id: WidgetId,
+        _layout_context: &mut LayoutContext,
+        box_constraint: BoxConstraint,
+    ) -> Size {
+        box_constraint.constrain(Size::new(20.0, 20.0))
+    }
+
+    fn children(&self) -> Vec<WidgetId> {
+        vec![]
+    }
+
+    fn interaction_invalidation(&self, _interaction: Interaction) -> Invalidation {
+        Invalidation::Layout
+    }
+
+    fn render(
+        &self,
+        _id: WidgetId,
+        _geometry: Geometry,
+        _text: Option<&FinalText>,
+        render_context: &mut RenderContext,
+    ) -> Option<u64> {
+        Some(render_context.next_tag_identifier())
+    }
+}
+
+/// Places its children side by side, giving them either a tight or a loose constraint, and
+/// takes the theme it is sent.
+struct Container {
+    children: Vec<WidgetId>,
+    tight_children: bool,
+    theme: Option<Theme>,
+}
+
+impl Container {
+    fn new(children: Vec<WidgetId>) -> Container {
+        Container {
+            children,
+            tight_children: false,
+            theme: None,
+        }
+    }
+}
+
+impl Widget for Container {
+    fn layout(
+        &self,
+        _id: WidgetId,
+        layout_context: &mut LayoutContext,
+        box_constraint: BoxConstraint,
+    ) -> Size {
+        for (index, &child) in self.children.iter().enumerate() {
+            let constraint = if self.tight_children {
+                BoxConstraint::tight(Size::new(200.0, 100.0))
+            } else {
+                BoxConstraint::new(Size::zero(), box_constraint.max)
+            };
+            layout_context.layout_widget(child, constraint);
+            layout_context.set_position(child, Position::new(index as f32 * 30.0, 0.0));
+        }
+        box_constraint.constrain(Size::new(200.0, 100.0))
+    }
+
+    fn children(&self) -> Vec<WidgetId> {
+        self.children.clone()
+    }
+
+    fn theme(&self) -> Option<&Theme> {
+        self.theme.as_ref()
+    }
+
+    fn update(&mut self, event: Box<dyn Any>) -> Option<Vec<WidgetId>> {
+        if let Ok(theme) = event.downcast::<Theme>() {
+            self.theme = Some(*theme);
+        }
+        None
+    }
+}
+
+/// Root -> outer -> boxed -> inner -> two leaves, where boxed is the relayout boundary of the
+/// widgets below it since outer gives it a tight constraint, and inner has its own theme.
+#[derive(Default)]
+struct Tree {
+    inner: Option<WidgetId>,
+}
+
+impl Application for Tree {
+    type Message = ();
+
+    fn build(&mut self, context: &mut WidgetContext<()>) -> WidgetId {
+        let leaves = vec![context.create_widget(Leaf), context.create_widget(Leaf)];
+        let inner = context.create_widget(Container {
+            theme: Some(Theme::default()),
+            ..Container::new(leaves)
+        });
+        let boxed = context.create_widget(Container::new(vec![inner]));
+        let outer = context.create_widget(Container {
+            tight_children: true,
+            ..Container::new(vec![boxed])
+        });
+        self.inner = Some(inner);
+        context.create_widget(Container::new(vec![outer]))
+    }
+}
+
+fn tree<'a, 'b>() -> Headless<'a, 'b, Tree> {
+    let mut headless = Headless::new(Tree::default(), Size::new(400.0, 300.0));
+    headless.step();
+    headless
+}
+
+#[test]
+fn first_frame_lays_out_every_widget() {
+    let headless = tree();
+    assert_eq!(
+        headless.layout_stats(),
+        LayoutStats {
+            laid_out: 6,
+            reused: 0
+        }
+    );
+}
+
+#[test]
+fn relayout_stops_at_the_relayout_boundary() {
+    let mut headless = tree();
+    headless.move_cursor(Position::new(5.0, 5.0));
+
+    // The hovered leaf and its ancestors up to the boxed widget are laid out again, while the
+    // root's and the other leaf's sizes are reused, even though inner has its own theme.
+    assert_eq!(
+        headless.layout_stats(),
+        LayoutStats {
+            laid_out: 3,
+            reused: 2
+        }
+    );
+}
+
+#[test]
+fn changing_a_widget_theme_lays_out_its_descendants() {
+    let mut headless = tree();
+    let inner = headless.application().inner.unwrap();
+    let mut theme = Theme::default();
+    theme.spacing.small += 1.0;
+
+    headless.send_message(inner, theme.clone());
+    // Inner, both leaves and the boxed widget they are bounded by.
+    assert_eq!(
+        headless.layout_stats(),
+        LayoutStats {
+            laid_out: 4,
+            reused: 1
+        }
+    );
+
+    // Sending the same theme again leaves the leaves alone.
+    headless.send_message(inner, theme);
+    assert_eq!(
+        headless.layout_stats(),
+        LayoutStats {
+            laid_out: 2,
+            reused: 3
+        }
+    );
+}
@@ -0,0 +1,154 @@
+use imagine::{
+    text::FinalText, Application, BoxConstraint, ColorF, Geometry, Headless, Interaction,
+    Invalidation, LayoutContext, PaintStats, Position, RenderContext, Size, Widget, WidgetContext,
+    WidgetId,
+};
+use webrender::api::units::*;
+use webrender::api::*;
+
+const SWATCH_SIZE: f32 = 50.0;
+
+/// A square drawn darker while hovered.
+struct Swatch {
+    hovered: bool,
+}
+
+impl Widget for Swatch {
+    fn layout(
+        &self,
+        _id: WidgetId,
+        _layout_context: &mut LayoutContext,
+        box_constraint: BoxConstraint,
+    ) -> Size {
+        box_constraint.constrain(Size::new(SWATCH_SIZE, SWATCH_SIZE))
+    }
+
+    fn children(&self) -> Vec<WidgetId> {
+        vec![]
+    }
+
+    fn handle_interaction(&mut self, interaction: Interaction) {
+        if let Interaction::Hovered(hovered) = interaction {
+            self.hovered = hovered;
+        }
+    }
+
+    fn interaction_invalidation(&self, _interaction: Interaction) -> Invalidation {
+        Invalidation::Paint
+    }
+
+    fn render(
+        &self,
+        _id: WidgetId,
+        geometry: Geometry,
+        _text: Option<&FinalText>,
+        render_context: &mut RenderContext,
+    ) -> Option<u64> {
+        let rect = LayoutRect::new(
+            LayoutPoint::new(geometry.position.x, geometry.position.y),
+            LayoutSize::new(geometry.size.width, geometry.size.height),
+        );
+        let identifier = render_context.next_tag_identifier();
+        let shade = if self.hovered { 0.5 } else { 1.0 };
+        render_context.builder.push_rect(
+            &CommonItemProperties::new(rect, render_context.current_space_and_clip),
+            ColorF::new(shade, shade, shade, 1.0),
+        );
+        Some(identifier)
+    }
+}
+
+/// Places its children side by side, `SWATCH_SIZE` apart from each other.
+struct Row {
+    children: Vec<WidgetId>,
+}
+
+impl Widget for Row {
+    fn layout(
+        &self,
+        _id: WidgetId,
+        layout_context: &mut LayoutContext,
+        box_constraint: BoxConstraint,
+    ) -> Size {
+        for (index, &child) in self.children.iter().enumerate() {
+            layout_context
+                .layout_widget(child, BoxConstraint::new(Size::zero(), box_constraint.max));
+            layout_context
+                .set_position(child, Position::new(index as f32 * SWATCH_SIZE * 2.0, 0.0));
+        }
+        box_constraint.max
+    }
+
+    fn children(&self) -> Vec<WidgetId> {
+        self.children.clone()
+    }
+}
+
+struct Swatches;
+
+impl Application for Swatches {
+    type Message = ();
+
+    fn build(&mut self, context: &mut WidgetContext<()>) -> WidgetId {
+        let children = (0..2)
+            .map(|_| context.create_widget(Swatch { hovered: false }))
+            .collect();
+        context.create_widget(Row { children })
+    }
+}
+
+fn swatches<'a, 'b>() -> Headless<'a, 'b, Swatches> {
+    let mut headless = Headless::new(Swatches, Size::new(400.0, 300.0));
+    headless.step();
+    headless
+}
+
+/// The center of the swatch at `index`.
+fn swatch_center(index: usize) -> Position {
+    Position::new(
+        index as f32 * SWATCH_SIZE * 2.0 + SWATCH_SIZE / 2.0,
+        SWATCH_SIZE / 2.0,
+    )
+}
+
+#[test]
+fn hover_paints_only_the_hovered_widget() {
+    let mut headless = swatches();
+    headless.move_cursor(swatch_center(1));
+
+    assert_eq!(headless.layout_stats().laid_out, 0);
+    // The row and the other swatch replay their display items.
+    assert_eq!(
+        headless.paint_stats(),
+        PaintStats {
+            painted: 1,
+            reused: 2
+        }
+    );
+
+    // Moving to the other swatch paints both of them.
+    headless.move_cursor(swatch_center(0));
+    assert_eq!(
+        headless.paint_stats(),
+        PaintStats {
+            painted: 2,
+            reused: 1
+        }
+    );
+}
+
+#[test]
+fn layout_paints_every_widget_it_lays_out() {
+    let mut headless = swatches();
+    headless.resize(Size::new(300.0, 200.0));
+    headless.step();
+
+    assert_eq!(headless.layout_stats().laid_out, 3);
+    assert_eq!(
+        headless.paint_stats(),
+        PaintStats {
+            painted: 3,
+            reused: 0
+        }
+    );
+}
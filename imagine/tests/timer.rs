@@ -0,0 +1,127 @@
+use imagine::{
+    Application, BoxConstraint, Headless, LayoutContext, Size, TimerId, Widget, WidgetContext,
+    WidgetId,
+};
+use std::time::Duration;
+
+struct Empty;
+
+impl Widget for Empty {
+    fn layout(
+        &self,
+        _id: WidgetId,
+        _layout_context: &mut LayoutContext,
+        box_constraint: BoxConstraint,
+    ) -> Size {
+        box_constraint.max
+    }
+
+    fn children(&self) -> Vec<WidgetId> {
+        vec![]
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Message {
+    TimedOut,
+    Tick,
+}
+
+/// Starts a timeout and an interval when it starts, cancelling the interval after
+/// `max_ticks`.
+#[derive(Default)]
+struct Timers {
+    timeout: Option<Duration>,
+    interval: Option<Duration>,
+    max_ticks: usize,
+    interval_id: Option<TimerId>,
+    messages: Vec<Message>,
+}
+
+impl Timers {
+    fn ticks(&self) -> usize {
+        self.messages
+            .iter()
+            .filter(|message| **message == Message::Tick)
+            .count()
+    }
+}
+
+impl Application for Timers {
+    type Message = Message;
+
+    fn build(&mut self, context: &mut WidgetContext<Message>) -> WidgetId {
+        context.create_widget(Empty)
+    }
+
+    fn on_start(&mut self, context: &mut WidgetContext<Message>) {
+        if let Some(timeout) = self.timeout {
+            context.set_timeout(timeout, Message::TimedOut);
+        }
+        if let Some(interval) = self.interval {
+            self.interval_id = Some(context.set_interval(interval, || Message::Tick));
+        }
+    }
+
+    fn handle_message(&mut self, message: Message, context: &mut WidgetContext<Message>) {
+        self.messages.push(message);
+        if self.ticks() == self.max_ticks {
+            if let Some(interval_id) = self.interval_id.take() {
+                context.cancel_timer(interval_id);
+            }
+        }
+    }
+}
+
+fn headless<'a, 'b>(timers: Timers) -> Headless<'a, 'b, Timers> {
+    let mut headless = Headless::new(timers, Size::new(100.0, 100.0));
+    headless.step();
+    headless
+}
+
+#[test]
+fn timeout_fires_once_when_due() {
+    let mut headless = headless(Timers {
+        timeout: Some(Duration::from_millis(200)),
+        ..Timers::default()
+    });
+
+    headless.advance_time(Duration::from_millis(199));
+    assert!(headless.application().messages.is_empty());
+    headless.advance_time(Duration::from_millis(1));
+    assert_eq!(headless.application().messages, vec![Message::TimedOut]);
+    headless.advance_time(Duration::from_millis(1000));
+    assert_eq!(headless.application().messages, vec![Message::TimedOut]);
+}
+
+#[test]
+fn interval_repeats_until_cancelled() {
+    let mut headless = headless(Timers {
+        interval: Some(Duration::from_millis(100)),
+        max_ticks: 3,
+        ..Timers::default()
+    });
+
+    for ticks in 1..=3 {
+        headless.advance_time(Duration::from_millis(100));
+        assert_eq!(headless.application().ticks(), ticks);
+    }
+    headless.advance_time(Duration::from_millis(100));
+    headless.advance_time(Duration::from_millis(100));
+    assert_eq!(headless.application().ticks(), 3);
+}
+
+#[test]
+fn late_interval_skips_the_ticks_it_missed() {
+    let mut headless = headless(Timers {
+        interval: Some(Duration::from_millis(100)),
+        max_ticks: 10,
+        ..Timers::default()
+    });
+
+    headless.advance_time(Duration::from_millis(350));
+    assert_eq!(headless.application().ticks(), 1);
+    // The next tick stays on the original schedule.
+    headless.advance_time(Duration::from_millis(50));
+    assert_eq!(headless.application().ticks(), 2);
+}
@@ -1,35 +1,62 @@
-use crate::{Center, Label, Padding};
+use crate::{Center, Label};
 use imagine::{
-    text::FinalText, BoxConstraint, Geometry, Interaction, LayoutContext, Position, RenderContext,
-    Size, Widget, WidgetContext, WidgetId,
+    text::FinalText, Animated, BoxConstraint, Easing, Geometry, Interaction, Invalidation,
+    LayoutContext, MouseButton, MouseEvent, Position, RenderContext, Size, Widget,
+    WidgetContext, WidgetId,
 };
+use std::time::Duration;
 use webrender::api::*;
 use webrender::api::units::*;
 
 pub struct Button {
-    pub color: (f32, f32, f32, f32),
+    /// The fill color, the theme's primary color if unset.
+    pub color: Option<ColorF>,
     hovered: bool,
     down: bool,
+    focused: bool,
+    // How far the hover shadow is faded in, from 0 to 1.
+    elevation: Animated<f32>,
     child: WidgetId,
 }
 
 impl Button {
     pub fn new<T: Into<String>, M: 'static + Send + Sync>(
         context: &mut WidgetContext<M>,
-        color: (f32, f32, f32, f32),
         text: T,
     ) -> Button {
-        let label = context.create_widget(Label::new(text));
-        let center = context.create_widget(Center::new(label));
-        let child = context.create_widget(Padding::new(10.0, 10.0, 10.0, 10.0, center));
+        Button::with_label(context, Label::new(text))
+    }
+
+    /// Creates a button around a label configured by the caller, e.g. with its own text style
+    /// and paint.
+    pub fn with_label<M: 'static + Send + Sync>(
+        context: &mut WidgetContext<M>,
+        label: Label,
+    ) -> Button {
+        let label = context.create_widget(label);
+        let child = context.create_widget(Center::new(label));
 
         Button {
-            color,
+            color: None,
             down: false,
             hovered: false,
+            focused: false,
+            elevation: Animated::new(0.0, Duration::from_millis(150), Easing::EaseOut),
             child,
         }
     }
+
+    pub fn with_color(mut self, color: ColorF) -> Button {
+        self.color = Some(color);
+        self
+    }
+
+    fn update_elevation(&mut self) {
+        let elevation = if self.hovered && !self.down { 1.0 } else { 0.0 };
+        if (self.elevation.target() - elevation).abs() > f32::EPSILON {
+            self.elevation.set(elevation);
+        }
+    }
 }
 
 impl Widget for Button {
@@ -39,19 +66,68 @@ impl Widget for Button {
         layout_context: &mut LayoutContext,
         box_constraint: BoxConstraint,
     ) -> Size {
-        let child_size = layout_context.layout_widget(
-            self.child,
-            BoxConstraint::new(Size::zero(), box_constraint.max),
+        let padding = layout_context.theme().spacing.medium;
+        let max = Size::new(
+            (box_constraint.max.width - padding * 2.0).max(0.0),
+            (box_constraint.max.height - padding * 2.0).max(0.0),
         );
-        layout_context.set_position(self.child, Position::zero());
-        box_constraint.constrain(child_size)
+        let child_size =
+            layout_context.layout_widget(self.child, BoxConstraint::new(Size::zero(), max));
+        layout_context.set_position(self.child, Position::new(padding, padding));
+        box_constraint.constrain(Size::new(
+            child_size.width + padding * 2.0,
+            child_size.height + padding * 2.0,
+        ))
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn activatable(&self) -> bool {
+        true
     }
 
     fn handle_interaction(&mut self, interaction: Interaction) {
         match interaction {
             Interaction::Hovered(hovered) => self.hovered = hovered,
-            Interaction::MouseDown => self.down = true,
-            Interaction::MouseUp => self.down = false,
+            Interaction::MouseDown(MouseEvent {
+                button: MouseButton::Left,
+                ..
+            }) => self.down = true,
+            Interaction::MouseUp(MouseEvent {
+                button: MouseButton::Left,
+                ..
+            }) => self.down = false,
+            Interaction::FocusGained => self.focused = true,
+            Interaction::FocusLost => {
+                self.focused = false;
+                self.down = false;
+            }
+            Interaction::KeyDown(key_event) => {
+                if key_event.is_activation() {
+                    self.down = true;
+                }
+            }
+            Interaction::KeyUp(key_event) => {
+                if key_event.is_activation() {
+                    self.down = false;
+                }
+            }
+            _ => {}
+        }
+        self.update_elevation();
+    }
+
+    fn interaction_invalidation(&self, _interaction: Interaction) -> Invalidation {
+        Invalidation::Paint
+    }
+
+    fn animate(&mut self, now: Duration) -> Invalidation {
+        if self.elevation.tick(now) {
+            Invalidation::Paint
+        } else {
+            Invalidation::None
         }
     }
 
@@ -76,8 +152,12 @@ impl Widget for Button {
         }
 
         let identifier = render_context.next_tag_identifier();
+        let theme = render_context.theme();
+        let color = self.color.unwrap_or(theme.palette.primary);
+        let accent = theme.palette.accent;
+        let shadow = theme.shadow;
 
-        let border_radius = BorderRadius::uniform(4.0);
+        let border_radius = BorderRadius::uniform(theme.corner_radius);
 
         let clip_id = render_context.builder.define_clip(
             &render_context.current_space_and_clip,
@@ -90,8 +170,6 @@ impl Widget for Button {
             None,
         );
 
-        let (r, g, b, a) = self.color;
-
         render_context
             .builder
             .push_rect(
@@ -102,17 +180,34 @@ impl Widget for Button {
                     hit_info: Some((identifier, 0)),
                     flags: PrimitiveFlags::empty(),
                 },
-                ColorF::new(r, g, b, a)
+                color
+            );
+
+        let elevation = self.elevation.get();
+        if elevation > 0.0 {
+            render_context.builder.push_box_shadow(
+                &CommonItemProperties::new(rect.inflate(4.0, 4.0), render_context.current_space_and_clip),
+                rect,
+                shadow.offset * elevation,
+                ColorF {
+                    a: shadow.color.a * elevation,
+                    ..shadow.color
+                },
+                shadow.blur_radius * elevation,
+                0.0,
+                border_radius,
+                BoxShadowClipMode::Outset,
             );
+        }
 
-        if self.hovered && !self.down {
+        if self.focused {
             render_context.builder.push_box_shadow(
                 &CommonItemProperties::new(rect.inflate(4.0, 4.0), render_context.current_space_and_clip),
                 rect,
-                LayoutVector2D::new(0.0, 3.0),
-                ColorF::new(0.0, 0.0, 0.0, 0.2),
-                4.0,
+                LayoutVector2D::zero(),
+                accent,
                 0.0,
+                2.0,
                 border_radius,
                 BoxShadowClipMode::Outset,
             );
@@ -1,26 +1,32 @@
 use imagine::{
-    text::FinalText, BoxConstraint, Geometry, Interaction, LayoutContext, RenderContext, Size,
-    Widget, WidgetId,
+    text::FinalText, BoxConstraint, Geometry, Interaction, Invalidation, LayoutContext,
+    MouseButton, MouseEvent, RenderContext, Size, Widget, WidgetId,
 };
 use webrender::api::*;
 use webrender::api::units::*;
 
 pub struct FillBox {
     pub size: Size,
-    pub color: (f32, f32, f32, f32),
+    /// The fill color, the theme's primary color if unset.
+    pub color: Option<ColorF>,
     hovered: bool,
     down: bool,
 }
 
 impl FillBox {
-    pub fn new(size: Size, color: (f32, f32, f32, f32)) -> FillBox {
+    pub fn new(size: Size) -> FillBox {
         FillBox {
             size,
-            color,
+            color: None,
             down: false,
             hovered: false,
         }
     }
+
+    pub fn with_color(mut self, color: ColorF) -> FillBox {
+        self.color = Some(color);
+        self
+    }
 }
 
 impl Widget for FillBox {
@@ -36,11 +42,22 @@ impl Widget for FillBox {
     fn handle_interaction(&mut self, interaction: Interaction) {
         match interaction {
             Interaction::Hovered(hovered) => self.hovered = hovered,
-            Interaction::MouseDown => self.down = true,
-            Interaction::MouseUp => self.down = false,
+            Interaction::MouseDown(MouseEvent {
+                button: MouseButton::Left,
+                ..
+            }) => self.down = true,
+            Interaction::MouseUp(MouseEvent {
+                button: MouseButton::Left,
+                ..
+            }) => self.down = false,
+            _ => {}
         }
     }
 
+    fn interaction_invalidation(&self, _interaction: Interaction) -> Invalidation {
+        Invalidation::Paint
+    }
+
     fn children(&self) -> Vec<WidgetId> {
         vec![]
     }
@@ -57,8 +74,11 @@ impl Widget for FillBox {
             LayoutSize::new(geometry.size.width, geometry.size.height),
         );
         let identifier = render_context.next_tag_identifier();
+        let theme = render_context.theme();
+        let mut color = self.color.unwrap_or(theme.palette.primary);
+        let shadow = theme.shadow;
 
-        let border_radius = BorderRadius::uniform(4.0);
+        let border_radius = BorderRadius::uniform(theme.corner_radius);
 
         let clip_id = render_context.builder.define_clip(
             &render_context.current_space_and_clip,
@@ -71,10 +91,8 @@ impl Widget for FillBox {
             None,
         );
 
-        let (r, g, b, mut a) = self.color;
-
         if self.down {
-            a = 0.5;
+            color.a = 0.5;
         }
 
         render_context
@@ -87,16 +105,16 @@ impl Widget for FillBox {
                     hit_info: Some((identifier, 0)),
                     flags: PrimitiveFlags::empty(),
                 },
-                ColorF::new(r, g, b, a)
+                color
             );
 
         if self.hovered {
             render_context.builder.push_box_shadow(
                 &CommonItemProperties::new(rect.inflate(4.0, 4.0), render_context.current_space_and_clip),
                 rect,
-                LayoutVector2D::new(0.0, 3.0),
-                ColorF::new(0.0, 0.0, 0.0, 0.2),
-                4.0,
+                shadow.offset,
+                shadow.color,
+                shadow.blur_radius,
                 0.0,
                 border_radius,
                 BoxShadowClipMode::Inset,
@@ -1,5 +1,7 @@
-use imagine::{BoxConstraint, LayoutContext, Position, Size, Widget, WidgetId};
+use crate::reorder::{insertion_index, move_item, ReorderEvent, ReorderPayload};
+use imagine::{BoxConstraint, DragPayload, LayoutContext, Position, Size, Widget, WidgetId};
 use std::any::Any;
+use std::sync::Mutex;
 
 #[derive(Copy, Clone)]
 pub enum FlexDirection {
@@ -68,6 +70,10 @@ pub struct Flex {
     children: Vec<FlexItem>,
     flex_direction: FlexDirection,
     flex_align: FlexAlign,
+    reorderable: bool,
+    // The start and length of every child along the major axis from the last layout.
+    extents: Mutex<Vec<(f32, f32)>>,
+    events: Vec<ReorderEvent>,
 }
 
 impl Flex {
@@ -80,8 +86,21 @@ impl Flex {
             children,
             flex_direction,
             flex_align,
+            reorderable: false,
+            extents: Mutex::new(Vec::new()),
+            events: Vec::new(),
         }
     }
+
+    /// Lets the user drag children to reorder them, reporting each move with `ReorderEvent`.
+    pub fn reorderable(mut self) -> Flex {
+        self.reorderable = true;
+        self
+    }
+
+    fn contains(&self, widget: WidgetId) -> bool {
+        self.children.iter().any(|item| item.widget() == widget)
+    }
 }
 
 impl Widget for Flex {
@@ -149,6 +168,7 @@ impl Widget for Flex {
         }
 
         let mut current_major = 0.0;
+        let mut extents = Vec::with_capacity(self.children.len());
         for child in self.children.iter().map(|item| item.widget()) {
             let size = layout_context.get_size(child);
             let minor = match self.flex_align {
@@ -162,9 +182,11 @@ impl Widget for Flex {
                 .major_minor_to_position(current_major, minor);
 
             layout_context.set_position(child, position);
+            extents.push((current_major, self.flex_direction.major_axis(size)));
 
             current_major += self.flex_direction.major_axis(size);
         }
+        *self.extents.lock().unwrap() = extents;
 
         self.flex_direction
             .major_minor_to_size(current_major, max_minor)
@@ -180,6 +202,43 @@ impl Widget for Flex {
             .collect()
     }
 
+    fn start_drag(&mut self, pressed: WidgetId) -> Option<DragPayload> {
+        if self.reorderable && self.contains(pressed) {
+            Some(Box::new(ReorderPayload(pressed)))
+        } else {
+            None
+        }
+    }
+
+    fn accepts_drop(&self, payload: &dyn Any) -> bool {
+        payload
+            .downcast_ref::<ReorderPayload>()
+            .is_some_and(|payload| self.contains(payload.0))
+    }
+
+    fn drop_payload(&mut self, payload: DragPayload, position: Position) {
+        if let Ok(payload) = payload.downcast::<ReorderPayload>() {
+            if let Some(from) = self.children.iter().position(|item| item.widget() == payload.0) {
+                let major = match self.flex_direction {
+                    FlexDirection::Horizontal => position.x,
+                    FlexDirection::Vertical => position.y,
+                };
+                let to = insertion_index(&self.extents.lock().unwrap(), major);
+                let to = move_item(&mut self.children, from, to);
+                if to != from {
+                    self.events.push(ReorderEvent { from, to });
+                }
+            }
+        }
+    }
+
+    fn take_events(&mut self) -> Vec<Box<dyn Any>> {
+        self.events
+            .drain(..)
+            .map(|event| Box::new(event) as Box<dyn Any>)
+            .collect()
+    }
+
     fn update(&mut self, event: Box<dyn Any>) -> Option<Vec<WidgetId>> {
         if let Ok(event) = event.downcast::<FlexEvent>() {
             match *event {
@@ -1,5 +1,7 @@
 use imagine::{
-    text::FinalText, BoxConstraint, Geometry, LayoutContext, RenderContext, Size, Widget, WidgetId,
+    text::{FinalText, ParagraphStyle, RichText, TextAlign, TextHit, TextPaint},
+    BoxConstraint, Geometry, Interaction, Invalidation, LayoutContext, MouseButton, MouseEvent,
+    RenderContext, Size, TextStyle, Widget, WidgetId,
 };
 use std::any::Any;
 use webrender::api::*;
@@ -7,15 +9,66 @@ use webrender::api::units::*;
 
 pub enum LabelMessage {
     SetText(String),
+    SetRichText(RichText),
+    SetStyle(TextStyle),
+    SetParagraph(ParagraphStyle),
+    SetPaint(TextPaint),
+}
+
+pub enum LabelEvent {
+    /// A span of a label made with `clickable_spans` was clicked.
+    SpanClicked(TextHit),
 }
 
 pub struct Label {
-    text: String,
+    text: RichText,
+    // The theme's body style and text color are used if these are unset.
+    style: Option<TextStyle>,
+    paragraph: ParagraphStyle,
+    paint: Option<TextPaint>,
+    clickable_spans: bool,
+    events: Vec<LabelEvent>,
 }
 
 impl Label {
     pub fn new<T: Into<String>>(text: T) -> Label {
-        Label { text: text.into() }
+        Label::rich(RichText::from(text.into()))
+    }
+
+    /// Creates a label whose spans each have their own color, size, weight and decorations.
+    pub fn rich(text: RichText) -> Label {
+        Label {
+            text,
+            style: None,
+            paragraph: ParagraphStyle::default(),
+            paint: None,
+            clickable_spans: false,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn with_style(mut self, style: TextStyle) -> Label {
+        self.style = Some(style);
+        self
+    }
+
+    /// Sets how the text wraps, aligns and truncates within the width given to the label.
+    pub fn with_paragraph(mut self, paragraph: ParagraphStyle) -> Label {
+        self.paragraph = paragraph;
+        self
+    }
+
+    /// Sets the color, opacity, antialiasing, shadow and decorations the text is drawn with.
+    pub fn with_paint(mut self, paint: TextPaint) -> Label {
+        self.paint = Some(paint);
+        self
+    }
+
+    /// Reports the span and character under the cursor with `LabelEvent::SpanClicked` when
+    /// the label is clicked, e.g. to follow links.
+    pub fn clickable_spans(mut self) -> Label {
+        self.clickable_spans = true;
+        self
     }
 }
 
@@ -30,10 +83,22 @@ impl Widget for Label {
         layout_context: &mut LayoutContext,
         box_constraint: BoxConstraint,
     ) -> Size {
-        let final_text = layout_context.layout_text(&self.text);
-        let width = final_text.width();
+        let max_width = box_constraint.max.width;
+        let style = self
+            .style
+            .as_ref()
+            .unwrap_or(&layout_context.theme().typography.body);
+        let final_text =
+            layout_context.layout_rich_text(&self.text, style, &self.paragraph, max_width);
+        // Aligned text is placed within the whole width, so the label has to fill it.
+        let width = if self.paragraph.align != TextAlign::Left && max_width.is_finite() {
+            max_width
+        } else {
+            final_text.width()
+        };
+        let size = Size::new(width, final_text.height());
         layout_context.set_text(id, final_text);
-        box_constraint.constrain(Size::new(width, 32.0))
+        box_constraint.constrain(size)
     }
 
     fn render(
@@ -50,21 +115,56 @@ impl Widget for Label {
         );
 
         if let Some(final_text) = text {
+            let color = render_context.theme().palette.text;
+            let paint = self
+                .paint
+                .unwrap_or_else(|| TextPaint::default().with_color(color));
             final_text.render(
                 &CommonItemProperties::new(rect, render_context.current_space_and_clip),
                 rect,
                 origin,
-                render_context.builder,
-                render_context.font_instance_key(),
+                &paint,
+                render_context,
             );
         }
-        None
+
+        if self.clickable_spans {
+            Some(render_context.next_tag_identifier())
+        } else {
+            None
+        }
+    }
+
+    fn handle_interaction(&mut self, interaction: Interaction) {
+        if let Interaction::MouseDown(MouseEvent {
+            button: MouseButton::Left,
+            text_hit: Some(hit),
+            ..
+        }) = interaction
+        {
+            self.events.push(LabelEvent::SpanClicked(hit));
+        }
+    }
+
+    fn interaction_invalidation(&self, _interaction: Interaction) -> Invalidation {
+        Invalidation::None
+    }
+
+    fn take_events(&mut self) -> Vec<Box<dyn Any>> {
+        self.events
+            .drain(..)
+            .map(|event| Box::new(event) as Box<dyn Any>)
+            .collect()
     }
 
     fn update(&mut self, event: Box<dyn Any>) -> Option<Vec<WidgetId>> {
         if let Ok(event) = event.downcast::<LabelMessage>() {
             match *event {
-                LabelMessage::SetText(text) => self.text = text,
+                LabelMessage::SetText(text) => self.text = RichText::from(text),
+                LabelMessage::SetRichText(text) => self.text = text,
+                LabelMessage::SetStyle(style) => self.style = Some(style),
+                LabelMessage::SetParagraph(paragraph) => self.paragraph = paragraph,
+                LabelMessage::SetPaint(paint) => self.paint = Some(paint),
             }
         }
         None
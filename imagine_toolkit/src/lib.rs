@@ -5,13 +5,21 @@ pub mod flex;
 pub mod label;
 pub mod list;
 pub mod padding;
+pub mod reorder;
+pub mod scroll;
+pub mod text_box;
+pub mod themed;
 
 pub use self::{
     button::Button,
     center::Center,
     fill_box::FillBox,
     flex::{Flex, FlexAlign, FlexDirection, FlexEvent, FlexItem},
-    label::{Label, LabelMessage},
+    label::{Label, LabelEvent, LabelMessage},
     list::List,
     padding::Padding,
+    reorder::ReorderEvent,
+    scroll::{Scroll, ScrollAxis, ScrollEvent, ScrollMessage},
+    text_box::{TextBox, TextBoxEvent, TextBoxMessage},
+    themed::{Themed, ThemedMessage},
 };
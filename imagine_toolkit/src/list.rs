@@ -1,12 +1,30 @@
-use imagine::{BoxConstraint, LayoutContext, Position, Size, Widget, WidgetId};
+use crate::reorder::{insertion_index, move_item, ReorderEvent, ReorderPayload};
+use imagine::{BoxConstraint, DragPayload, LayoutContext, Position, Size, Widget, WidgetId};
+use std::any::Any;
+use std::sync::Mutex;
 
 pub struct List {
     widgets: Vec<WidgetId>,
+    reorderable: bool,
+    // The vertical start and height of every child from the last layout.
+    extents: Mutex<Vec<(f32, f32)>>,
+    events: Vec<ReorderEvent>,
 }
 
 impl List {
     pub fn new(widgets: Vec<WidgetId>) -> List {
-        List { widgets }
+        List {
+            widgets,
+            reorderable: false,
+            extents: Mutex::new(Vec::new()),
+            events: Vec::new(),
+        }
+    }
+
+    /// Lets the user drag children to reorder them, reporting each move with `ReorderEvent`.
+    pub fn reorderable(mut self) -> List {
+        self.reorderable = true;
+        self
     }
 }
 
@@ -22,6 +40,7 @@ impl Widget for List {
         box_constraint: BoxConstraint,
     ) -> Size {
         let mut current_y = 0.0;
+        let mut extents = Vec::with_capacity(self.widgets.len());
         for child in &self.widgets {
             let child_size = layout_context.layout_widget(
                 *child,
@@ -32,8 +51,43 @@ impl Widget for List {
             );
 
             layout_context.set_position(*child, Position::new(0.0, current_y));
+            extents.push((current_y, child_size.height));
             current_y += child_size.height;
         }
+        *self.extents.lock().unwrap() = extents;
         box_constraint.constrain(Size::new(box_constraint.max.width, current_y))
     }
+
+    fn start_drag(&mut self, pressed: WidgetId) -> Option<DragPayload> {
+        if self.reorderable && self.widgets.contains(&pressed) {
+            Some(Box::new(ReorderPayload(pressed)))
+        } else {
+            None
+        }
+    }
+
+    fn accepts_drop(&self, payload: &dyn Any) -> bool {
+        payload
+            .downcast_ref::<ReorderPayload>()
+            .is_some_and(|payload| self.widgets.contains(&payload.0))
+    }
+
+    fn drop_payload(&mut self, payload: DragPayload, position: Position) {
+        if let Ok(payload) = payload.downcast::<ReorderPayload>() {
+            if let Some(from) = self.widgets.iter().position(|widget| *widget == payload.0) {
+                let to = insertion_index(&self.extents.lock().unwrap(), position.y);
+                let to = move_item(&mut self.widgets, from, to);
+                if to != from {
+                    self.events.push(ReorderEvent { from, to });
+                }
+            }
+        }
+    }
+
+    fn take_events(&mut self) -> Vec<Box<dyn Any>> {
+        self.events
+            .drain(..)
+            .map(|event| Box::new(event) as Box<dyn Any>)
+            .collect()
+    }
 }
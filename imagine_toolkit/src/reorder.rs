@@ -0,0 +1,82 @@
+use imagine::WidgetId;
+
+/// Emitted by a reorderable `List` or `Flex` to its `EventListener` after a child is dragged
+/// from index `from` to index `to`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ReorderEvent {
+    pub from: usize,
+    pub to: usize,
+}
+
+/// The drag payload for a child being reordered within its parent.
+pub(crate) struct ReorderPayload(pub(crate) WidgetId);
+
+/// Finds where a child dropped at `major` should be inserted, given the start and length of
+/// every child along the main axis.
+pub(crate) fn insertion_index(extents: &[(f32, f32)], major: f32) -> usize {
+    extents
+        .iter()
+        .take_while(|(start, length)| major > start + length / 2.0)
+        .count()
+}
+
+/// Moves the item at `from` so that it ends up before the item that was at `to`, returning
+/// the item's new index.
+pub(crate) fn move_item<T>(items: &mut Vec<T>, from: usize, to: usize) -> usize {
+    let item = items.remove(from);
+    let to = if to > from { to - 1 } else { to };
+    items.insert(to, item);
+    to
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXTENTS: [(f32, f32); 3] = [(0.0, 10.0), (10.0, 20.0), (30.0, 10.0)];
+
+    #[test]
+    fn insertion_index_splits_children_at_their_middle() {
+        assert_eq!(insertion_index(&EXTENTS, -5.0), 0);
+        assert_eq!(insertion_index(&EXTENTS, 4.0), 0);
+        assert_eq!(insertion_index(&EXTENTS, 6.0), 1);
+        assert_eq!(insertion_index(&EXTENTS, 19.0), 1);
+        assert_eq!(insertion_index(&EXTENTS, 21.0), 2);
+        // Past the end of the last child.
+        assert_eq!(insertion_index(&EXTENTS, 100.0), 3);
+    }
+
+    #[test]
+    fn move_item_forward() {
+        let mut items = vec!['a', 'b', 'c', 'd'];
+        // Before 'd', which is after 'a' was removed.
+        assert_eq!(move_item(&mut items, 0, 3), 2);
+        assert_eq!(items, vec!['b', 'c', 'a', 'd']);
+    }
+
+    #[test]
+    fn move_item_back() {
+        let mut items = vec!['a', 'b', 'c', 'd'];
+        assert_eq!(move_item(&mut items, 3, 1), 1);
+        assert_eq!(items, vec!['a', 'd', 'b', 'c']);
+    }
+
+    #[test]
+    fn move_item_past_the_end() {
+        let mut items = vec!['a', 'b', 'c', 'd'];
+        let to = insertion_index(
+            &[(0.0, 10.0), (10.0, 10.0), (20.0, 10.0), (30.0, 10.0)],
+            50.0,
+        );
+        assert_eq!(move_item(&mut items, 1, to), 3);
+        assert_eq!(items, vec!['a', 'c', 'd', 'b']);
+    }
+
+    #[test]
+    fn move_item_onto_itself() {
+        let mut items = vec!['a', 'b', 'c'];
+        assert_eq!(move_item(&mut items, 1, 1), 1);
+        assert_eq!(move_item(&mut items, 1, 2), 1);
+        assert_eq!(items, vec!['a', 'b', 'c']);
+    }
+}
@@ -0,0 +1,347 @@
+use imagine::{
+    text::FinalText, BoxConstraint, Geometry, Interaction, Invalidation, LayoutContext,
+    Position, RenderContext, Size, Widget, WidgetId,
+};
+use std::any::Any;
+use std::sync::Mutex;
+use webrender::api::*;
+use webrender::api::units::*;
+
+const SCROLLBAR_SIZE: f32 = 8.0;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ScrollAxis {
+    Horizontal,
+    Vertical,
+    Both,
+}
+
+impl ScrollAxis {
+    fn horizontal(self) -> bool {
+        matches!(self, ScrollAxis::Horizontal | ScrollAxis::Both)
+    }
+
+    fn vertical(self) -> bool {
+        matches!(self, ScrollAxis::Vertical | ScrollAxis::Both)
+    }
+}
+
+pub enum ScrollMessage {
+    SetOffset(Position),
+    /// Asks the scroll view to report its offset with `ScrollEvent::Offset`.
+    QueryOffset,
+}
+
+/// Emitted by `Scroll` to its `EventListener` when the offset changes or is queried.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScrollEvent {
+    Offset(Position),
+}
+
+#[derive(Copy, Clone)]
+struct Extent {
+    viewport: Size,
+    content: Size,
+}
+
+impl Extent {
+    fn clamp(&self, offset: Position) -> Position {
+        let max_x = (self.content.width - self.viewport.width).max(0.0);
+        let max_y = (self.content.height - self.viewport.height).max(0.0);
+        Position::new(offset.x.clamp(0.0, max_x), offset.y.clamp(0.0, max_y))
+    }
+}
+
+/// Shows the part of its child that the scroll offset moves into view, with a scrollbar for
+/// each axis that scrolls.
+///
+/// The child is moved by layout and hidden outside of the viewport with a clip rather than a
+/// webrender scroll frame, because hit testing and replaying recorded display items work in
+/// layout coordinates and would otherwise miss the offset the renderer applies.
+pub struct Scroll {
+    child: WidgetId,
+    axis: ScrollAxis,
+    offset: Position,
+    // Sizes from the last layout, needed to clamp the offset while handling input.
+    extent: Mutex<Extent>,
+    hovered: bool,
+    // The scrollbar whose thumb is being dragged, either `Horizontal` or `Vertical`.
+    thumb: Option<ScrollAxis>,
+    // Whether the last interaction moved the offset.
+    scrolled: bool,
+    events: Vec<ScrollEvent>,
+}
+
+impl Scroll {
+    pub fn new(child: WidgetId, axis: ScrollAxis) -> Scroll {
+        Scroll {
+            child,
+            axis,
+            offset: Position::zero(),
+            extent: Mutex::new(Extent {
+                viewport: Size::zero(),
+                content: Size::zero(),
+            }),
+            hovered: false,
+            thumb: None,
+            scrolled: false,
+            events: Vec::new(),
+        }
+    }
+
+    fn extent(&self) -> Extent {
+        *self.extent.lock().unwrap()
+    }
+
+    fn scroll_by(&mut self, x: f32, y: f32) {
+        let extent = self.extent();
+        let current = extent.clamp(self.offset);
+        let mut offset = current;
+        if self.axis.horizontal() {
+            offset.x += x;
+        }
+        if self.axis.vertical() {
+            offset.y += y;
+        }
+        let offset = extent.clamp(offset);
+
+        self.offset = offset;
+        if offset != current {
+            self.scrolled = true;
+            self.events.push(ScrollEvent::Offset(offset));
+        }
+    }
+}
+
+impl Widget for Scroll {
+    fn layout(
+        &self,
+        _id: WidgetId,
+        layout_context: &mut LayoutContext,
+        box_constraint: BoxConstraint,
+    ) -> Size {
+        let horizontal = self.axis.horizontal();
+        let vertical = self.axis.vertical();
+        let gutter = Size::new(
+            if vertical { SCROLLBAR_SIZE } else { 0.0 },
+            if horizontal { SCROLLBAR_SIZE } else { 0.0 },
+        );
+
+        let child_constraint = BoxConstraint::new(
+            Size::new(
+                if horizontal {
+                    0.0
+                } else {
+                    (box_constraint.min.width - gutter.width).max(0.0)
+                },
+                if vertical {
+                    0.0
+                } else {
+                    (box_constraint.min.height - gutter.height).max(0.0)
+                },
+            ),
+            Size::new(
+                if horizontal {
+                    std::f32::INFINITY
+                } else {
+                    box_constraint.max.width - gutter.width
+                },
+                if vertical {
+                    std::f32::INFINITY
+                } else {
+                    box_constraint.max.height - gutter.height
+                },
+            ),
+        );
+
+        let content = layout_context.layout_widget(self.child, child_constraint);
+        let size = box_constraint.constrain(Size::new(
+            content.width + gutter.width,
+            content.height + gutter.height,
+        ));
+        let extent = Extent {
+            viewport: Size::new(size.width - gutter.width, size.height - gutter.height),
+            content,
+        };
+
+        let offset = extent.clamp(self.offset);
+        layout_context.set_position(self.child, Position::new(-offset.x, -offset.y));
+        *self.extent.lock().unwrap() = extent;
+
+        size
+    }
+
+    fn children(&self) -> Vec<WidgetId> {
+        vec![self.child]
+    }
+
+    fn children_clip(&self, geometry: Geometry) -> Option<Geometry> {
+        // The children are hidden under the scrollbars, as `render` clips them to the viewport.
+        Some(Geometry::new(geometry.position, self.extent().viewport))
+    }
+
+    fn scrollable(&self) -> bool {
+        true
+    }
+
+    fn claims_drag(&self) -> bool {
+        true
+    }
+
+    fn handle_interaction(&mut self, interaction: Interaction) {
+        self.scrolled = false;
+        match interaction {
+            Interaction::Hovered(hovered) => self.hovered = hovered,
+            Interaction::MouseDown(mouse_event) => {
+                // Presses that reach the scroll view itself rather than its child are on the
+                // scrollbars.
+                let viewport = self.extent().viewport;
+                self.thumb = if self.axis.vertical() && mouse_event.position.x >= viewport.width {
+                    Some(ScrollAxis::Vertical)
+                } else if self.axis.horizontal() && mouse_event.position.y >= viewport.height {
+                    Some(ScrollAxis::Horizontal)
+                } else {
+                    None
+                };
+            }
+            Interaction::MouseUp(_) => self.thumb = None,
+            Interaction::Wheel(delta) => {
+                // Scrolling right moves toward the end of the content, scrolling up toward
+                // its start.
+                let (x, y) = delta.pixels();
+                if self.axis == ScrollAxis::Horizontal && x == 0.0 {
+                    self.scroll_by(-y, 0.0);
+                } else {
+                    self.scroll_by(x, -y);
+                }
+            }
+            Interaction::MouseMove(mouse_move) => {
+                let extent = self.extent();
+                match self.thumb {
+                    // The thumb follows the pointer, so the content moves by as much more as
+                    // it is larger than the viewport.
+                    Some(ScrollAxis::Vertical) => self.scroll_by(
+                        0.0,
+                        mouse_move.dy * extent.content.height / extent.viewport.height,
+                    ),
+                    Some(_) => self.scroll_by(
+                        mouse_move.dx * extent.content.width / extent.viewport.width,
+                        0.0,
+                    ),
+                    // Dragging the content moves it along with the pointer.
+                    None => self.scroll_by(-mouse_move.dx, -mouse_move.dy),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn interaction_invalidation(&self, interaction: Interaction) -> Invalidation {
+        match interaction {
+            Interaction::Hovered(_) => Invalidation::Paint,
+            // Scrolling moves the child, which is positioned by layout.
+            Interaction::Wheel(_) | Interaction::MouseMove(_) if self.scrolled => {
+                Invalidation::Layout
+            }
+            _ => Invalidation::None,
+        }
+    }
+
+    fn take_events(&mut self) -> Vec<Box<dyn Any>> {
+        self.events
+            .drain(..)
+            .map(|event| Box::new(event) as Box<dyn Any>)
+            .collect()
+    }
+
+    fn render(
+        &self,
+        _id: WidgetId,
+        geometry: Geometry,
+        _text: Option<&FinalText>,
+        render_context: &mut RenderContext,
+    ) -> Option<u64> {
+        let extent = self.extent();
+        let offset = extent.clamp(self.offset);
+        let origin = LayoutPoint::new(geometry.position.x, geometry.position.y);
+        let rect = LayoutRect::new(
+            origin,
+            LayoutSize::new(geometry.size.width, geometry.size.height),
+        );
+        let viewport = LayoutRect::new(
+            origin,
+            LayoutSize::new(extent.viewport.width, extent.viewport.height),
+        );
+        let identifier = render_context.next_tag_identifier();
+        let space_and_clip = render_context.current_space_and_clip;
+
+        render_context.builder.push_rect(
+            &CommonItemProperties {
+                clip_rect: rect,
+                clip_id: space_and_clip.clip_id,
+                spatial_id: space_and_clip.spatial_id,
+                hit_info: Some((identifier, 0)),
+                flags: PrimitiveFlags::empty(),
+            },
+            ColorF::TRANSPARENT,
+        );
+
+        let palette = render_context.theme().palette;
+        let thumb_color = if self.hovered {
+            palette.scrollbar_hovered
+        } else {
+            palette.scrollbar
+        };
+
+        if self.axis.vertical() && extent.content.height > extent.viewport.height {
+            let scale = extent.viewport.height / extent.content.height;
+            let thumb = LayoutRect::new(
+                LayoutPoint::new(origin.x + extent.viewport.width, origin.y + offset.y * scale),
+                LayoutSize::new(SCROLLBAR_SIZE, extent.viewport.height * scale),
+            );
+            render_context.builder.push_rect(
+                &CommonItemProperties::new(thumb, space_and_clip),
+                thumb_color,
+            );
+        }
+
+        if self.axis.horizontal() && extent.content.width > extent.viewport.width {
+            let scale = extent.viewport.width / extent.content.width;
+            let thumb = LayoutRect::new(
+                LayoutPoint::new(origin.x + offset.x * scale, origin.y + extent.viewport.height),
+                LayoutSize::new(extent.viewport.width * scale, SCROLLBAR_SIZE),
+            );
+            render_context.builder.push_rect(
+                &CommonItemProperties::new(thumb, space_and_clip),
+                thumb_color,
+            );
+        }
+
+        // Everything rendered for the children is clipped to the viewport.
+        let clip_id = render_context.builder.define_clip(
+            &space_and_clip,
+            viewport,
+            Vec::<ComplexClipRegion>::new(),
+            None,
+        );
+        render_context.current_space_and_clip = SpaceAndClipInfo {
+            spatial_id: space_and_clip.spatial_id,
+            clip_id,
+        };
+
+        Some(identifier)
+    }
+
+    fn update(&mut self, event: Box<dyn Any>) -> Option<Vec<WidgetId>> {
+        if let Ok(event) = event.downcast::<ScrollMessage>() {
+            match *event {
+                ScrollMessage::SetOffset(offset) => self.offset = offset,
+                ScrollMessage::QueryOffset => {
+                    let offset = self.extent().clamp(self.offset);
+                    self.events.push(ScrollEvent::Offset(offset));
+                }
+            }
+        }
+        None
+    }
+}
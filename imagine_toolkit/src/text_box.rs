@@ -0,0 +1,423 @@
+use imagine::{
+    text::{FinalText, TextPaint},
+    BoxConstraint, Geometry, Interaction, Invalidation, KeyEvent, LayoutContext, RenderContext,
+    Size, TextStyle, VirtualKeyCode, Widget, WidgetId,
+};
+use std::any::Any;
+use webrender::api::*;
+use webrender::api::units::*;
+
+pub enum TextBoxMessage {
+    SetText(String),
+    SetPaint(TextPaint),
+}
+
+/// Emitted by `TextBox` to its `EventListener`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TextBoxEvent {
+    Changed(String),
+    Submitted(String),
+}
+
+pub struct TextBox {
+    text: String,
+    // Byte offsets into `text`. The selection spans from `anchor` to `caret`.
+    caret: usize,
+    anchor: usize,
+    // The theme's body style and text color are used if these are unset.
+    style: Option<TextStyle>,
+    paint: Option<TextPaint>,
+    focused: bool,
+    hovered: bool,
+    // Whether the last interaction changed the text, which has to be laid out again.
+    edited: bool,
+    events: Vec<TextBoxEvent>,
+}
+
+impl TextBox {
+    pub fn new<T: Into<String>>(text: T) -> TextBox {
+        let text = text.into();
+        let caret = text.len();
+        TextBox {
+            text,
+            caret,
+            anchor: caret,
+            style: None,
+            paint: None,
+            focused: false,
+            hovered: false,
+            edited: false,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn with_style(mut self, style: TextStyle) -> TextBox {
+        self.style = Some(style);
+        self
+    }
+
+    /// Sets the color, opacity, antialiasing, shadow and decorations the text is drawn with.
+    pub fn with_paint(mut self, paint: TextPaint) -> TextBox {
+        self.paint = Some(paint);
+        self
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn selection(&self) -> (usize, usize) {
+        (self.caret.min(self.anchor), self.caret.max(self.anchor))
+    }
+
+    fn has_selection(&self) -> bool {
+        self.caret != self.anchor
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        if !self.has_selection() {
+            return false;
+        }
+        let (start, end) = self.selection();
+        self.text.replace_range(start..end, "");
+        self.caret = start;
+        self.anchor = start;
+        true
+    }
+
+    fn insert(&mut self, text: &str) {
+        self.delete_selection();
+        self.text.insert_str(self.caret, text);
+        self.caret += text.len();
+        self.anchor = self.caret;
+        self.changed();
+    }
+
+    fn changed(&mut self) {
+        self.edited = true;
+        self.events.push(TextBoxEvent::Changed(self.text.clone()));
+    }
+
+    fn previous_boundary(&self, index: usize) -> usize {
+        self.text[..index]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    fn next_boundary(&self, index: usize) -> usize {
+        self.text[index..]
+            .chars()
+            .next()
+            .map_or(index, |c| index + c.len_utf8())
+    }
+
+    fn move_caret(&mut self, caret: usize, extend: bool) {
+        self.caret = caret;
+        if !extend {
+            self.anchor = caret;
+        }
+    }
+
+    fn handle_key(&mut self, key_event: KeyEvent) {
+        let extend = key_event.modifiers.shift;
+        match key_event.key {
+            Some(VirtualKeyCode::Back) => {
+                if !self.delete_selection() {
+                    if self.caret == 0 {
+                        return;
+                    }
+                    let start = self.previous_boundary(self.caret);
+                    self.text.replace_range(start..self.caret, "");
+                    self.caret = start;
+                    self.anchor = start;
+                }
+                self.changed();
+            }
+            Some(VirtualKeyCode::Delete) => {
+                if !self.delete_selection() {
+                    if self.caret == self.text.len() {
+                        return;
+                    }
+                    let end = self.next_boundary(self.caret);
+                    self.text.replace_range(self.caret..end, "");
+                    self.anchor = self.caret;
+                }
+                self.changed();
+            }
+            Some(VirtualKeyCode::Left) => {
+                let caret = if self.has_selection() && !extend {
+                    self.selection().0
+                } else {
+                    self.previous_boundary(self.caret)
+                };
+                self.move_caret(caret, extend);
+            }
+            Some(VirtualKeyCode::Right) => {
+                let caret = if self.has_selection() && !extend {
+                    self.selection().1
+                } else {
+                    self.next_boundary(self.caret)
+                };
+                self.move_caret(caret, extend);
+            }
+            Some(VirtualKeyCode::Home) => self.move_caret(0, extend),
+            Some(VirtualKeyCode::End) => self.move_caret(self.text.len(), extend),
+            Some(VirtualKeyCode::A) if key_event.modifiers.ctrl => {
+                self.anchor = 0;
+                self.caret = self.text.len();
+            }
+            Some(VirtualKeyCode::Escape) => self.anchor = self.caret,
+            Some(VirtualKeyCode::Return) | Some(VirtualKeyCode::NumpadEnter) => {
+                self.events.push(TextBoxEvent::Submitted(self.text.clone()));
+            }
+            _ => {}
+        }
+    }
+
+    fn char_index(&self, byte_index: usize) -> usize {
+        self.text[..byte_index].chars().count()
+    }
+}
+
+impl Widget for TextBox {
+    fn layout(
+        &self,
+        id: WidgetId,
+        layout_context: &mut LayoutContext,
+        box_constraint: BoxConstraint,
+    ) -> Size {
+        let padding = layout_context.theme().spacing.small;
+        let style = self
+            .style
+            .as_ref()
+            .unwrap_or(&layout_context.theme().typography.body);
+        let final_text = layout_context.layout_text(&self.text, style);
+        let text_width = final_text.width() + padding * 2.0;
+        let text_height = final_text.height() + padding * 2.0;
+        layout_context.set_text(id, final_text);
+
+        let width = if box_constraint.max.width.is_finite() {
+            box_constraint.max.width
+        } else {
+            text_width
+        };
+        box_constraint.constrain(Size::new(width, text_height))
+    }
+
+    fn children(&self) -> Vec<WidgetId> {
+        vec![]
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn handles_key(&self, key_event: KeyEvent) -> bool {
+        let modifiers = key_event.modifiers;
+        // Keys pressed without a command modifier type text, edit it or move the caret.
+        if !(modifiers.ctrl || modifiers.alt || modifiers.logo) {
+            return true;
+        }
+        match key_event.key {
+            Some(VirtualKeyCode::A) => modifiers.ctrl,
+            // Copy, cut and paste.
+            Some(VirtualKeyCode::C) | Some(VirtualKeyCode::X) | Some(VirtualKeyCode::V) => {
+                !modifiers.alt
+            }
+            _ => false,
+        }
+    }
+
+    fn handle_interaction(&mut self, interaction: Interaction) {
+        self.edited = false;
+        match interaction {
+            Interaction::Hovered(hovered) => self.hovered = hovered,
+            Interaction::FocusGained => self.focused = true,
+            Interaction::FocusLost => {
+                self.focused = false;
+                self.anchor = self.caret;
+            }
+            Interaction::KeyDown(key_event) => self.handle_key(key_event),
+            Interaction::Character(character) => {
+                if !character.is_control() {
+                    self.insert(character.encode_utf8(&mut [0; 4]));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn interaction_invalidation(&self, interaction: Interaction) -> Invalidation {
+        if self.edited {
+            return Invalidation::Layout;
+        }
+        match interaction {
+            // The caret and selection are drawn from the text laid out before.
+            Interaction::Hovered(_)
+            | Interaction::FocusGained
+            | Interaction::FocusLost
+            | Interaction::KeyDown(_)
+            | Interaction::Character(_) => Invalidation::Paint,
+            _ => Invalidation::None,
+        }
+    }
+
+    fn copy(&self) -> Option<String> {
+        if self.has_selection() {
+            let (start, end) = self.selection();
+            Some(self.text[start..end].to_string())
+        } else {
+            None
+        }
+    }
+
+    fn cut(&mut self) -> Option<String> {
+        let text = self.copy();
+        if self.delete_selection() {
+            self.changed();
+        }
+        text
+    }
+
+    fn paste(&mut self, text: &str) {
+        let text = text
+            .chars()
+            .filter(|character| !character.is_control())
+            .collect::<String>();
+        if !text.is_empty() || self.has_selection() {
+            self.insert(&text);
+        }
+    }
+
+    fn take_events(&mut self) -> Vec<Box<dyn Any>> {
+        self.events
+            .drain(..)
+            .map(|event| Box::new(event) as Box<dyn Any>)
+            .collect()
+    }
+
+    fn render(
+        &self,
+        _id: WidgetId,
+        geometry: Geometry,
+        text: Option<&FinalText>,
+        render_context: &mut RenderContext,
+    ) -> Option<u64> {
+        let rect = LayoutRect::new(
+            LayoutPoint::new(geometry.position.x, geometry.position.y),
+            LayoutSize::new(geometry.size.width, geometry.size.height),
+        );
+        let identifier = render_context.next_tag_identifier();
+        let theme = render_context.theme();
+        let palette = theme.palette;
+        let padding = theme.spacing.small;
+        let border_radius = BorderRadius::uniform(theme.corner_radius);
+
+        let clip_id = render_context.builder.define_clip(
+            &render_context.current_space_and_clip,
+            rect,
+            vec![ComplexClipRegion::new(
+                rect,
+                border_radius,
+                ClipMode::Clip,
+            )],
+            None,
+        );
+        let properties = CommonItemProperties {
+            clip_rect: rect,
+            clip_id,
+            spatial_id: render_context.current_space_and_clip.spatial_id,
+            hit_info: None,
+            flags: PrimitiveFlags::empty(),
+        };
+
+        render_context.builder.push_rect(
+            &CommonItemProperties {
+                hit_info: Some((identifier, 0)),
+                ..properties
+            },
+            palette.surface,
+        );
+
+        let origin = LayoutPoint::new(
+            geometry.position.x + padding,
+            geometry.position.y + padding,
+        );
+
+        if let Some(final_text) = text {
+            let text_height = final_text.height();
+            if self.focused && self.has_selection() {
+                let (start, end) = self.selection();
+                let (start, end) = (self.char_index(start), self.char_index(end));
+                for area in final_text.selection_rects(start, end) {
+                    let selection = LayoutRect::new(
+                        LayoutPoint::new(origin.x + area.origin.x, origin.y + area.origin.y),
+                        area.size,
+                    );
+                    render_context.builder.push_rect(
+                        &CommonItemProperties {
+                            clip_rect: selection,
+                            ..properties
+                        },
+                        palette.selection,
+                    );
+                }
+            }
+
+            let paint = self
+                .paint
+                .unwrap_or_else(|| TextPaint::default().with_color(palette.text));
+            final_text.render(&properties, rect, origin, &paint, render_context);
+
+            if self.focused {
+                let x = final_text.caret_offset(self.char_index(self.caret));
+                let caret = LayoutRect::new(
+                    LayoutPoint::new(origin.x + x, origin.y),
+                    LayoutSize::new(1.0, text_height),
+                );
+                render_context.builder.push_rect(
+                    &CommonItemProperties {
+                        clip_rect: caret,
+                        ..properties
+                    },
+                    palette.text,
+                );
+            }
+        }
+
+        let border_color = if self.focused {
+            palette.accent
+        } else if self.hovered {
+            palette.border_hovered
+        } else {
+            palette.border
+        };
+        render_context.builder.push_box_shadow(
+            &properties,
+            rect,
+            LayoutVector2D::zero(),
+            border_color,
+            0.0,
+            1.0,
+            border_radius,
+            BoxShadowClipMode::Inset,
+        );
+
+        Some(identifier)
+    }
+
+    fn update(&mut self, event: Box<dyn Any>) -> Option<Vec<WidgetId>> {
+        if let Ok(event) = event.downcast::<TextBoxMessage>() {
+            match *event {
+                TextBoxMessage::SetText(text) => {
+                    self.caret = text.len();
+                    self.anchor = self.caret;
+                    self.text = text;
+                }
+                TextBoxMessage::SetPaint(paint) => self.paint = Some(paint),
+            }
+        }
+        None
+    }
+}
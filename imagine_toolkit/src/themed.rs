@@ -0,0 +1,48 @@
+use imagine::{BoxConstraint, LayoutContext, Position, Size, Theme, Widget, WidgetId};
+use std::any::Any;
+
+pub enum ThemedMessage {
+    SetTheme(Theme),
+}
+
+/// Draws its child and everything below it with its own theme instead of the application's.
+pub struct Themed {
+    theme: Theme,
+    child: WidgetId,
+}
+
+impl Themed {
+    pub fn new(theme: Theme, child: WidgetId) -> Themed {
+        Themed { theme, child }
+    }
+}
+
+impl Widget for Themed {
+    fn layout(
+        &self,
+        _id: WidgetId,
+        layout_context: &mut LayoutContext,
+        box_constraint: BoxConstraint,
+    ) -> Size {
+        let size = layout_context.layout_widget(self.child, box_constraint);
+        layout_context.set_position(self.child, Position::zero());
+        size
+    }
+
+    fn children(&self) -> Vec<WidgetId> {
+        vec![self.child]
+    }
+
+    fn theme(&self) -> Option<&Theme> {
+        Some(&self.theme)
+    }
+
+    fn update(&mut self, event: Box<dyn Any>) -> Option<Vec<WidgetId>> {
+        if let Ok(event) = event.downcast::<ThemedMessage>() {
+            match *event {
+                ThemedMessage::SetTheme(theme) => self.theme = theme,
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,133 @@
+use imagine::{
+    text::FinalText, Application, BoxConstraint, EventListener, Geometry, Headless, LayoutContext,
+    Position, RenderContext, Size, WheelDelta, Widget, WidgetContext, WidgetId,
+};
+use imagine_toolkit::{Scroll, ScrollAxis, ScrollEvent, ScrollMessage};
+
+/// Content five times as tall as the window.
+struct Tall;
+
+impl Widget for Tall {
+    fn layout(
+        &self,
+        _id: WidgetId,
+        _layout_context: &mut LayoutContext,
+        box_constraint: BoxConstraint,
+    ) -> Size {
+        box_constraint.constrain(Size::new(100.0, 1000.0))
+    }
+
+    fn children(&self) -> Vec<WidgetId> {
+        vec![]
+    }
+
+    fn render(
+        &self,
+        _id: WidgetId,
+        _geometry: Geometry,
+        _text: Option<&FinalText>,
+        render_context: &mut RenderContext,
+    ) -> Option<u64> {
+        Some(render_context.next_tag_identifier())
+    }
+}
+
+#[derive(Default)]
+struct Scrolling {
+    scroll: Option<WidgetId>,
+    content: Option<WidgetId>,
+    offsets: Vec<Position>,
+}
+
+impl Application for Scrolling {
+    type Message = ScrollEvent;
+
+    fn build(&mut self, context: &mut WidgetContext<ScrollEvent>) -> WidgetId {
+        let content = context.create_widget(Tall);
+        let scroll = context.create_widget(Scroll::new(content, ScrollAxis::Vertical));
+        context.add_event_listener(
+            scroll,
+            EventListener::new(|event: &ScrollEvent| event.clone()),
+        );
+        self.scroll = Some(scroll);
+        self.content = Some(content);
+        scroll
+    }
+
+    fn handle_message(&mut self, message: ScrollEvent, _context: &mut WidgetContext<ScrollEvent>) {
+        let ScrollEvent::Offset(offset) = message;
+        self.offsets.push(offset);
+    }
+}
+
+/// A 200 by 200 window that shows the top of content 1000 high, so the content scrolls by
+/// up to 800 and the scrollbar thumb is 40 high.
+fn scrolling<'a, 'b>() -> Headless<'a, 'b, Scrolling> {
+    let mut headless = Headless::new(Scrolling::default(), Size::new(200.0, 200.0));
+    headless.step();
+    headless
+}
+
+fn content_y(headless: &Headless<Scrolling>) -> f32 {
+    let content = headless.application().content.unwrap();
+    headless.geometry(content).unwrap().position.y
+}
+
+#[test]
+fn wheel_scrolling_is_clamped_to_the_content() {
+    let mut headless = scrolling();
+    headless.move_cursor(Position::new(50.0, 100.0));
+
+    headless.scroll(WheelDelta::Pixels(0.0, -300.0));
+    assert_eq!(content_y(&headless), -300.0);
+    headless.scroll(WheelDelta::Pixels(0.0, -1000.0));
+    assert_eq!(content_y(&headless), -800.0);
+
+    // Scrolling past the end changes nothing, which lays nothing out.
+    headless.scroll(WheelDelta::Pixels(0.0, -10.0));
+    assert_eq!(headless.layout_stats().laid_out, 0);
+
+    headless.scroll(WheelDelta::Lines(0.0, 100.0));
+    assert_eq!(content_y(&headless), 0.0);
+    assert_eq!(
+        headless.application().offsets,
+        vec![
+            Position::new(0.0, 300.0),
+            Position::new(0.0, 800.0),
+            Position::zero()
+        ]
+    );
+}
+
+#[test]
+fn set_offset_is_clamped_and_reported_by_query_offset() {
+    let mut headless = scrolling();
+    let scroll = headless.application().scroll.unwrap();
+
+    headless.send_message(scroll, ScrollMessage::SetOffset(Position::new(0.0, 5000.0)));
+    assert_eq!(content_y(&headless), -800.0);
+    headless.send_message(scroll, ScrollMessage::SetOffset(Position::new(0.0, 250.0)));
+    assert_eq!(content_y(&headless), -250.0);
+    assert!(headless.application().offsets.is_empty());
+
+    headless.send_message(scroll, ScrollMessage::QueryOffset);
+    assert_eq!(
+        headless.application().offsets,
+        vec![Position::new(0.0, 250.0)]
+    );
+}
+
+#[test]
+fn dragging_the_content_moves_it_with_the_pointer() {
+    let mut headless = scrolling();
+    headless.drag(Position::new(50.0, 150.0), Position::new(50.0, 50.0));
+    assert_eq!(content_y(&headless), -100.0);
+}
+
+#[test]
+fn dragging_the_thumb_moves_it_with_the_pointer() {
+    let mut headless = scrolling();
+    headless.drag(Position::new(104.0, 20.0), Position::new(104.0, 70.0));
+    // The thumb moved by 50 of the 200 high track, which shows a fifth of the content.
+    assert_eq!(content_y(&headless), -250.0);
+}
@@ -0,0 +1,145 @@
+use imagine::{
+    Application, EventListener, Headless, InputEvent, ModifiersState, Size, VirtualKeyCode,
+    WidgetContext, WidgetId,
+};
+use imagine_toolkit::{TextBox, TextBoxEvent};
+
+struct Editor {
+    initial: &'static str,
+    events: Vec<TextBoxEvent>,
+}
+
+impl Application for Editor {
+    type Message = TextBoxEvent;
+
+    fn build(&mut self, context: &mut WidgetContext<TextBoxEvent>) -> WidgetId {
+        let text_box = context.create_widget(TextBox::new(self.initial));
+        context.add_event_listener(
+            text_box,
+            EventListener::new(|event: &TextBoxEvent| event.clone()),
+        );
+        text_box
+    }
+
+    fn handle_message(
+        &mut self,
+        message: TextBoxEvent,
+        _context: &mut WidgetContext<TextBoxEvent>,
+    ) {
+        self.events.push(message);
+    }
+}
+
+/// A headless window with a focused text box holding `initial`, the caret at its end.
+fn editor<'a, 'b>(initial: &'static str) -> Headless<'a, 'b, Editor> {
+    let editor = Editor {
+        initial,
+        events: vec![],
+    };
+    let mut headless = Headless::new(editor, Size::new(400.0, 100.0));
+    headless.step();
+    headless.press_key(VirtualKeyCode::Tab);
+    headless
+}
+
+fn type_text(headless: &mut Headless<Editor>, text: &str) {
+    for character in text.chars() {
+        headless.inject(InputEvent::Character(character));
+    }
+}
+
+fn changed(text: &str) -> TextBoxEvent {
+    TextBoxEvent::Changed(text.to_string())
+}
+
+#[test]
+fn backspace_and_delete_remove_whole_characters() {
+    let mut headless = editor("a€éb");
+    headless.press_key(VirtualKeyCode::Back);
+    headless.press_key(VirtualKeyCode::Back);
+    headless.press_key(VirtualKeyCode::Home);
+    headless.press_key(VirtualKeyCode::Right);
+    headless.press_key(VirtualKeyCode::Delete);
+    // Nothing is left after the caret, so this changes nothing.
+    headless.press_key(VirtualKeyCode::Delete);
+
+    assert_eq!(
+        headless.application().events,
+        vec![changed("a€é"), changed("a€"), changed("a")]
+    );
+}
+
+#[test]
+fn typing_replaces_the_selection() {
+    let mut headless = editor("hello wörld");
+    headless.set_modifiers(ModifiersState {
+        shift: true,
+        ..ModifiersState::default()
+    });
+    for _ in 0..5 {
+        headless.press_key(VirtualKeyCode::Left);
+    }
+    headless.set_modifiers(ModifiersState::default());
+    type_text(&mut headless, "you");
+    assert_eq!(
+        headless.application().events.last(),
+        Some(&changed("hello you"))
+    );
+
+    headless.set_modifiers(ModifiersState {
+        ctrl: true,
+        ..ModifiersState::default()
+    });
+    headless.press_key(VirtualKeyCode::A);
+    headless.set_modifiers(ModifiersState::default());
+    type_text(&mut headless, "ü");
+    assert_eq!(headless.application().events.last(), Some(&changed("ü")));
+}
+
+#[test]
+fn return_submits_the_text() {
+    let mut headless = editor("");
+    type_text(&mut headless, "hi");
+    headless.press_key(VirtualKeyCode::Return);
+
+    assert_eq!(
+        headless.application().events,
+        vec![
+            changed("h"),
+            changed("hi"),
+            TextBoxEvent::Submitted("hi".to_string())
+        ]
+    );
+}
+
+#[test]
+fn only_text_changes_lay_the_text_box_out_again() {
+    let mut headless = editor("text");
+    headless.key_down(VirtualKeyCode::Left);
+    assert_eq!(headless.layout_stats().laid_out, 0);
+    assert_eq!(headless.paint_stats().painted, 1);
+    headless.key_up(VirtualKeyCode::Left);
+
+    type_text(&mut headless, "s");
+    assert_eq!(headless.layout_stats().laid_out, 1);
+}
+
+#[test]
+fn clipboard_shortcuts_cut_and_paste_the_selection() {
+    let mut headless = editor("copy");
+    headless.set_modifiers(ModifiersState {
+        ctrl: true,
+        ..ModifiersState::default()
+    });
+    headless.press_key(VirtualKeyCode::A);
+    headless.press_key(VirtualKeyCode::X);
+    assert_eq!(headless.clipboard_contents(), Some("copy".to_string()));
+    assert_eq!(headless.application().events.last(), Some(&changed("")));
+
+    headless.press_key(VirtualKeyCode::V);
+    headless.press_key(VirtualKeyCode::V);
+    assert_eq!(
+        headless.application().events.last(),
+        Some(&changed("copycopy"))
+    );
+}